@@ -5,6 +5,7 @@ use tokio_tungstenite::{connect_async, tungstenite::Message};
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 enum ClientMessage {
+    Hello { protocol_version: u32, client_name: String, capabilities: Vec<String> },
     JoinQueue { player_name: String },
     PlayCard { card: Card },
     RequestState,
@@ -52,6 +53,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     let (mut write, mut read) = ws_stream.split();
     
+    // Introduce ourselves, the server handles nothing before this
+    let hello = ClientMessage::Hello {
+        protocol_version: 1,
+        client_name: "test_client".to_string(),
+        capabilities: Vec::new(),
+    };
+    write.send(Message::Text(serde_json::to_string(&hello)?)).await?;
+    
     // Send join queue message
     let join_msg = ClientMessage::JoinQueue {
         player_name: "TestPlayer".to_string(),
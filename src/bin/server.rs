@@ -1,25 +1,31 @@
 use std::env;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{mpsc, Semaphore};
 use tokio::time::interval;
-use tokio_tungstenite::accept_async;
+use tokio_tungstenite::accept_hdr_async_with_config;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
 use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 use uuid::Uuid;
 
 // Import from main crate
 use kseri::server::{
-    Connection, ConnectionHandler, ConnectionManager,
-    SessionManager, Broadcaster,
+    AccountStore, AutoPlay, ClockConfig, Connection, ConnectionHandler, ConnectionLimits, ConnectionManager,
+    LimitConfig, RatingStore, SessionManager, SessionStore, Broadcaster,
 };
 
 const DEFAULT_PORT: u16 = 8080;
 const MAX_CONNECTIONS: usize = 1000;
 const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+const BOT_FALLBACK_INTERVAL: Duration = Duration::from_secs(1);
+const CLOCK_INTERVAL: Duration = Duration::from_millis(250);
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -42,9 +48,112 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         .and_then(|c| c.parse().ok())
         .unwrap_or(MAX_CONNECTIONS);
     
+    // Seat bots at tables that have waited this many seconds for players
+    let bot_fallback = env::var("KSERI_BOT_FALLBACK_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(Duration::from_secs);
+    
+    // Seconds per move; the other clock settings only apply when this is set
+    let turn_clock = env::var("KSERI_TURN_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(|secs| {
+            let defaults = ClockConfig::default();
+            ClockConfig {
+                per_move: Duration::from_secs(secs),
+                per_game: env::var("KSERI_GAME_SECS").ok()
+                    .and_then(|s| s.parse().ok())
+                    .map(Duration::from_secs),
+                warning: env::var("KSERI_WARNING_SECS").ok()
+                    .and_then(|s| s.parse().ok())
+                    .map(Duration::from_secs)
+                    .unwrap_or(defaults.warning),
+                max_timeouts: env::var("KSERI_MAX_TIMEOUTS").ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(defaults.max_timeouts),
+                auto_play: match env::var("KSERI_AUTO_PLAY").as_deref() {
+                    Ok("bot") => AutoPlay::Bot,
+                    _ => AutoPlay::LowestCard,
+                },
+            }
+        });
+    
+    // Reverse proxies trusted to name the client in `X-Forwarded-For`, comma-separated
+    let trusted_proxies = match env::var("KSERI_TRUSTED_PROXIES") {
+        Ok(list) => list.split(',')
+            .map(|ip| ip.trim().parse::<IpAddr>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid KSERI_TRUSTED_PROXIES: {}", e))?,
+        Err(_) => Vec::new(),
+    };
+    
+    // Abuse limits for every connection
+    let limit_defaults = LimitConfig::default();
+    let limit_config = LimitConfig {
+        max_frame_bytes: env::var("KSERI_MAX_FRAME_BYTES").ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(limit_defaults.max_frame_bytes),
+        messages_per_sec: env::var("KSERI_MESSAGES_PER_SEC").ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(limit_defaults.messages_per_sec),
+        burst: env::var("KSERI_MESSAGE_BURST").ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(limit_defaults.burst),
+        max_connections_per_ip: env::var("KSERI_MAX_CONNECTIONS_PER_IP").ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(limit_defaults.max_connections_per_ip),
+        max_malformed: env::var("KSERI_MAX_MALFORMED").ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(limit_defaults.max_malformed),
+        ban_duration: env::var("KSERI_BAN_SECS").ok()
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(limit_defaults.ban_duration),
+        trusted_proxies,
+    };
+    limit_config.validate().map_err(|e| format!("Invalid connection limits: {}", e))?;
+    let limits = Arc::new(ConnectionLimits::new(limit_config));
+    
+    // Save a record of every finished game here
+    let record_dir = env::var("KSERI_RECORD_DIR").ok().map(PathBuf::from);
+    if let Some(dir) = &record_dir {
+        std::fs::create_dir_all(dir)?;
+        info!("Saving game records to {}", dir.display());
+    }
+    
     // Create server components
     let connection_manager = Arc::new(ConnectionManager::new());
-    let session_manager = Arc::new(SessionManager::new());
+    let mut session_manager = SessionManager::new();
+    if let Some(wait) = bot_fallback {
+        session_manager = session_manager.with_bot_fallback(wait);
+    }
+    if let Some(dir) = record_dir {
+        session_manager = session_manager.with_record_dir(dir);
+    }
+    if let Some(config) = turn_clock {
+        session_manager = session_manager.with_turn_clock(config);
+    }
+    
+    // Keep player ratings in this file across restarts
+    if let Ok(path) = env::var("KSERI_RATINGS_FILE") {
+        session_manager = session_manager.with_ratings(RatingStore::open(&path)?);
+        info!("Ratings stored in {}", path);
+    }
+    
+    // Player accounts and the key their login tokens are signed with
+    if let Ok(path) = env::var("KSERI_ACCOUNTS_FILE") {
+        session_manager = session_manager.with_accounts(AccountStore::open(&path)?);
+        info!("Accounts stored in {}", path);
+    }
+    
+    // Snapshot running games here and pick them up again on the next start
+    if let Ok(dir) = env::var("KSERI_STATE_DIR") {
+        session_manager = session_manager.with_store(SessionStore::new(&dir)?);
+        let restored = session_manager.restore_sessions()?;
+        info!("Restored {} sessions from {}", restored, dir);
+    }
+    let session_manager = Arc::new(session_manager);
     let connection_semaphore = Arc::new(Semaphore::new(max_connections));
     
     // Create broadcaster (currently unused but ready for optimized broadcasting)
@@ -59,6 +168,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     
     info!("Kseri WebSocket server listening on {}", addr);
     info!("Max connections: {}", max_connections);
+    info!("Connection limits: {:?}", limits.config);
     
     // Spawn cleanup task
     let cleanup_connections = connection_manager.clone();
@@ -74,28 +184,78 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                 info!("Cleaned up {} timed out connections", timed_out.len());
             }
             
-            // Cleanup expired sessions
-            cleanup_sessions.cleanup_expired_sessions().await;
+            // Cleanup expired sessions, telling players their room closed
+            let messages = cleanup_sessions.cleanup_expired_sessions().await;
+            for (conn_id, msg) in cleanup_sessions.sequence(messages).await {
+                cleanup_connections.send(&conn_id, msg).ok();
+            }
         }
     });
     
-    // Main accept loop
+    // Spawn bot fallback task
+    if let Some(wait) = bot_fallback {
+        info!("Waiting tables get bots after {:?}", wait);
+        
+        let fallback_connections = connection_manager.clone();
+        let fallback_sessions = session_manager.clone();
+        tokio::spawn(async move {
+            let mut interval = interval(BOT_FALLBACK_INTERVAL);
+            loop {
+                interval.tick().await;
+                
+                let messages = fallback_sessions.fill_waiting_with_bots().await;
+                for (conn_id, msg) in fallback_sessions.sequence(messages).await {
+                    fallback_connections.send(&conn_id, msg).ok();
+                }
+            }
+        });
+    }
+    
+    // Spawn turn clock task
+    if let Some(config) = turn_clock {
+        info!("Turns are timed: {:?}", config);
+        
+        let clock_connections = connection_manager.clone();
+        let clock_sessions = session_manager.clone();
+        tokio::spawn(async move {
+            let mut interval = interval(CLOCK_INTERVAL);
+            loop {
+                interval.tick().await;
+                
+                let messages = clock_sessions.enforce_turn_clocks().await;
+                for (conn_id, msg) in clock_sessions.sequence(messages).await {
+                    clock_connections.send(&conn_id, msg).ok();
+                }
+            }
+        });
+    }
+    
+    // Main accept loop, until Ctrl-C
     loop {
-        let (stream, addr) = listener.accept().await?;
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = tokio::signal::ctrl_c() => break,
+        };
         
         // Acquire connection permit
         let permit = connection_semaphore.clone().acquire_owned().await?;
         
         let connections = connection_manager.clone();
         let sessions = session_manager.clone();
+        let limits = limits.clone();
         
         tokio::spawn(async move {
-            if let Err(e) = handle_connection(stream, addr, connections, sessions).await {
+            if let Err(e) = handle_connection(stream, addr, connections, sessions, limits).await {
                 error!("Error handling connection from {}: {}", addr, e);
             }
             drop(permit); // Release permit when connection ends
         });
     }
+    
+    let saved = session_manager.persist_all().await;
+    info!("Shutting down, saved {} sessions", saved);
+    
+    Ok(())
 }
 
 async fn handle_connection(
@@ -103,12 +263,65 @@ async fn handle_connection(
     addr: SocketAddr,
     connection_manager: Arc<ConnectionManager>,
     session_manager: Arc<SessionManager>,
+    limits: Arc<ConnectionLimits>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     info!("New TCP connection from {}", addr);
     
-    // Accept WebSocket
-    let ws_stream = accept_async(stream).await?;
-    info!("WebSocket connection established from {}", addr);
+    // Turn away banned addresses and those with too many connections open before
+    // spending a handshake on them; the socket is simply closed. Behind a trusted proxy
+    // the client is only known from the request headers, so it is checked there instead
+    // and refused with an HTTP error rather than upgraded.
+    let peer = addr.ip();
+    let proxied = limits.config.trusted_proxies.contains(&peer);
+    let mut client_ip = peer;
+    let mut permit = None;
+    if !proxied {
+        match limits.admit(peer) {
+            Ok(admitted) => permit = Some(admitted),
+            Err(reason) => {
+                info!("Refused connection from {}: {:?}", addr, reason);
+                return Ok(());
+            }
+        }
+    }
+    // The error type is fixed by tungstenite's `Callback`
+    #[allow(clippy::result_large_err)]
+    let check_client = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        if !proxied {
+            return Ok(response);
+        }
+        let forwarded_for = request.headers().get("x-forwarded-for").and_then(|value| value.to_str().ok());
+        client_ip = limits.config.client_ip(peer, forwarded_for);
+        match limits.admit(client_ip) {
+            Ok(admitted) => {
+                permit = Some(admitted);
+                Ok(response)
+            }
+            Err(reason) => {
+                info!("Refused connection from {} via {}: {:?}", client_ip, addr, reason);
+                let mut refused = ErrorResponse::new(None);
+                *refused.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+                Err(refused)
+            }
+        }
+    };
+    
+    // Accept WebSocket. Frames a little over the limit get a `FrameTooLarge` answer,
+    // anything far over it drops the connection before it is buffered.
+    let max_size = limits.config.max_frame_bytes.saturating_mul(4);
+    let ws_config = WebSocketConfig {
+        max_message_size: Some(max_size),
+        max_frame_size: Some(max_size),
+        ..Default::default()
+    };
+    let ws_stream = match accept_hdr_async_with_config(stream, check_client, Some(ws_config)).await {
+        Ok(ws_stream) => ws_stream,
+        // Refused by the proxied check, already logged
+        Err(_) if permit.is_none() => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let _permit = permit;
+    info!("WebSocket connection established from {}", client_ip);
     
     // Create connection
     let connection_id = Uuid::new_v4();
@@ -125,7 +338,8 @@ async fn handle_connection(
         rx,
         connection_manager,
         session_manager,
-    );
+    )
+    .with_limits(limits, client_ip);
     
     handler.run().await;
     
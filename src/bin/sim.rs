@@ -0,0 +1,390 @@
+use std::env;
+use std::process;
+use std::thread;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use kseri::components::card::{Card, PlayerId};
+use kseri::server::{
+    unique_max, Bot, Difficulty, GameConfig, GameMode, HeuristicBot, KseriGameState, PlayerView, RuleVariant,
+    SearchBot, SearchBudget,
+};
+
+const DEFAULT_GAMES: u32 = 1000;
+const USAGE: &str = "\
+Usage: kseri-sim [options]
+
+  --games N          games to play (default 1000)
+  --agents A,B,...   one agent per team: random, heuristic, easy, medium, hard, expert
+                     (default heuristic,random)
+  --variant V        classic, pisti or house (default classic)
+  --mode M           heads-up, partnership, three or four (default heads-up)
+  --threads N        worker threads (default: all cores)
+  --seed S           seed of the first game (default 0); search agents run their full
+                     iteration budget without a time limit, so a seed always reproduces a run";
+
+/// An agent named on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Agent {
+    Random,
+    Heuristic,
+    Search(Difficulty),
+}
+
+impl Agent {
+    fn parse(name: &str) -> Result<Self, String> {
+        Ok(match name {
+            "random" => Agent::Random,
+            "heuristic" => Agent::Heuristic,
+            "easy" => Agent::Search(Difficulty::Easy),
+            "medium" => Agent::Search(Difficulty::Medium),
+            "hard" => Agent::Search(Difficulty::Hard),
+            "expert" => Agent::Search(Difficulty::Expert),
+            other => return Err(format!("Unknown agent '{}'", other)),
+        })
+    }
+
+    /// The agent for one seat of one game; everything it does follows from `seed`
+    fn create(&self, seed: u64) -> Box<dyn Bot> {
+        match self {
+            Agent::Random => Box::new(RandomBot(StdRng::seed_from_u64(seed))),
+            Agent::Heuristic => Box::new(HeuristicBot),
+            // A time limit would make the result depend on the machine's load
+            Agent::Search(difficulty) => {
+                let budget = difficulty.budget();
+                Box::new(SearchBot::with_seed(SearchBudget { time_limit: None, ..budget }, seed))
+            }
+        }
+    }
+}
+
+/// Baseline that plays any card from its hand
+struct RandomBot(StdRng);
+
+impl Bot for RandomBot {
+    fn choose_card(&mut self, view: &PlayerView) -> Option<Card> {
+        view.hand.choose(&mut self.0).copied()
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    games: u32,
+    agents: Vec<(String, Agent)>,
+    config: GameConfig,
+    threads: usize,
+    seed: u64,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            games: DEFAULT_GAMES,
+            agents: Vec::new(),
+            config: GameConfig::default(),
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            seed: 0,
+        };
+        let mut agent_names = "heuristic,random".to_string();
+
+        while let Some(flag) = args.next() {
+            let value = args.next().ok_or_else(|| format!("Missing value for {}", flag))?;
+            match flag.as_str() {
+                "--games" => options.games = value.parse().map_err(|_| format!("Invalid game count '{}'", value))?,
+                "--agents" => agent_names = value,
+                "--variant" => {
+                    options.config.variant = match value.as_str() {
+                        "classic" => RuleVariant::Classic,
+                        "pisti" => RuleVariant::Pisti,
+                        "house" => RuleVariant::HouseValues,
+                        other => return Err(format!("Unknown variant '{}'", other)),
+                    }
+                }
+                "--mode" => {
+                    options.config.mode = match value.as_str() {
+                        "heads-up" => GameMode::HeadsUp,
+                        "partnership" => GameMode::Partnership,
+                        "three" => GameMode::ThreePlayer,
+                        "four" => GameMode::FourPlayer,
+                        other => return Err(format!("Unknown mode '{}'", other)),
+                    }
+                }
+                "--threads" => options.threads = value.parse().map_err(|_| format!("Invalid thread count '{}'", value))?,
+                "--seed" => options.seed = value.parse().map_err(|_| format!("Invalid seed '{}'", value))?,
+                other => return Err(format!("Unknown option '{}'", other)),
+            }
+        }
+
+        options.agents = agent_names.split(',')
+            .map(|name| Agent::parse(name).map(|agent| (name.to_string(), agent)))
+            .collect::<Result<_, _>>()?;
+
+        let teams = options.config.mode.team_count();
+        if options.agents.len() != teams {
+            return Err(format!("{:?} needs {} agents, got {}", options.config.mode, teams, options.agents.len()));
+        }
+        if options.threads == 0 {
+            return Err("Need at least one thread".to_string());
+        }
+
+        Ok(options)
+    }
+}
+
+/// Totals over a batch of games, indexed by team
+#[derive(Debug, Default, PartialEq)]
+struct Stats {
+    games: u32,
+    wins: Vec<u32>,
+    draws: u32,
+    scores: Vec<u64>,
+    kseris: Vec<u32>,
+    majority_decided: u32,
+    last_capture_decided: u32,
+}
+
+impl Stats {
+    fn new(teams: usize) -> Self {
+        Self {
+            wins: vec![0; teams],
+            scores: vec![0; teams],
+            kseris: vec![0; teams],
+            ..Default::default()
+        }
+    }
+
+    fn merge(&mut self, other: Stats) {
+        self.games += other.games;
+        self.draws += other.draws;
+        self.majority_decided += other.majority_decided;
+        self.last_capture_decided += other.last_capture_decided;
+        for team in 0..self.wins.len() {
+            self.wins[team] += other.wins[team];
+            self.scores[team] += other.scores[team];
+            self.kseris[team] += other.kseris[team];
+        }
+    }
+}
+
+fn main() {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let started = Instant::now();
+    let teams = options.config.mode.team_count();
+
+    // Worker `w` plays every game whose index is `w` modulo the thread count
+    let stats = thread::scope(|scope| {
+        let workers: Vec<_> = (0..options.threads)
+            .map(|worker| {
+                let options = &options;
+                scope.spawn(move || {
+                    let mut stats = Stats::new(teams);
+                    for game in (worker as u32..options.games).step_by(options.threads) {
+                        play_game(options, options.seed.wrapping_add(game as u64), &mut stats);
+                    }
+                    stats
+                })
+            })
+            .collect();
+
+        let mut total = Stats::new(teams);
+        for worker in workers {
+            total.merge(worker.join().expect("simulation thread panicked"));
+        }
+        total
+    });
+
+    report(&options, &stats, started.elapsed().as_secs_f64());
+}
+
+fn play_game(options: &Options, seed: u64, stats: &mut Stats) {
+    let mode = options.config.mode;
+    let seats = mode.seat_count();
+
+    // Rotate the opening lead so no agent always plays first
+    let lead = PlayerId((seed % seats as u64) as u8);
+    let mut state = KseriGameState::with_opening_lead(seed, options.config, lead);
+    let mut bots: Vec<Box<dyn Bot>> = (0..seats)
+        .map(|seat| options.agents[mode.team_of(seat)].1.create(seed.wrapping_mul(31).wrapping_add(seat as u64)))
+        .collect();
+
+    // Cards handed to the last capturer at the end of the deal
+    let mut leftover = Vec::new();
+    while !state.game_over {
+        let seat = state.current_turn;
+        let card = bots[seat.index()].choose_card(&state.player_view(seat)).expect("agent chose no card");
+        let table_before = state.table_cards.clone();
+        let result = state.play_card(seat, card).expect("agent made an invalid play");
+
+        if result.game_ended && result.captured_cards.is_empty() {
+            leftover = table_before;
+            leftover.push(card);
+        }
+    }
+
+    let scores = state.calculate_scores();
+    let winner = state.get_winning_team();
+    stats.games += 1;
+    match winner {
+        Some(team) => stats.wins[team] += 1,
+        None => stats.draws += 1,
+    }
+    for seat in 0..seats {
+        let team = mode.team_of(seat);
+        stats.kseris[team] += state.kseri_count[seat] + state.jack_kseri_count[seat];
+    }
+    for (team, score) in scores.iter().enumerate() {
+        stats.scores[team] += *score as u64;
+    }
+
+    // Would the result differ without the majority bonus?
+    let mut without_majority = vec![0; mode.team_count()];
+    for (seat, points) in state.seat_points().into_iter().enumerate() {
+        without_majority[mode.team_of(seat)] += points;
+    }
+    if unique_max(&without_majority) != winner {
+        stats.majority_decided += 1;
+    }
+
+    // ... or if the last table had stayed where it was?
+    if let (false, Some(last)) = (leftover.is_empty(), state.last_capture_player) {
+        let mut without_last = state.clone();
+        let pile = &mut without_last.player_scores[last.index()];
+        pile.truncate(pile.len() - leftover.len());
+        if unique_max(&without_last.calculate_scores()) != winner {
+            stats.last_capture_decided += 1;
+        }
+    }
+}
+
+/// Win rate and the half-width of its 95% confidence interval, by the normal
+/// approximation to the binomial
+fn win_rate(wins: u32, games: u32) -> (f64, f64) {
+    let games = games.max(1) as f64;
+    let rate = wins as f64 / games;
+    (rate, 1.96 * (rate * (1.0 - rate) / games).sqrt())
+}
+
+fn report(options: &Options, stats: &Stats, seconds: f64) {
+    let games = stats.games.max(1) as f64;
+    let percent = |count: u32| 100.0 * count as f64 / games;
+
+    println!(
+        "{} games of {:?} {:?} in {:.1}s ({:.0} games/s)",
+        stats.games, options.config.variant, options.config.mode, seconds, stats.games as f64 / seconds.max(f64::EPSILON),
+    );
+    println!();
+    println!("{:<12} {:>9} {:>10} {:>10} {:>12}", "agent", "win rate", "95% CI", "avg score", "kseris/game");
+
+    for (team, (name, _)) in options.agents.iter().enumerate() {
+        let (rate, margin) = win_rate(stats.wins[team], stats.games);
+        println!(
+            "{:<12} {:>8.1}% {:>9}% {:>10.2} {:>12.3}",
+            name,
+            100.0 * rate,
+            format!("±{:.1}", 100.0 * margin),
+            stats.scores[team] as f64 / games,
+            stats.kseris[team] as f64 / games,
+        );
+    }
+
+    println!();
+    println!("draws                       {:>5.1}%", percent(stats.draws));
+    println!("decided by majority bonus   {:>5.1}%", percent(stats.majority_decided));
+    println!("decided by last capture     {:>5.1}%", percent(stats.last_capture_decided));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.games, DEFAULT_GAMES);
+        assert_eq!(options.agents, vec![("heuristic".to_string(), Agent::Heuristic), ("random".to_string(), Agent::Random)]);
+        assert_eq!(options.config, GameConfig::default());
+
+        let options = parse(&["--games", "10", "--agents", "expert,easy,random", "--mode", "three", "--variant", "pisti", "--seed", "7", "--threads", "2"]).unwrap();
+        assert_eq!(options.games, 10);
+        assert_eq!(options.agents[0].1, Agent::Search(Difficulty::Expert));
+        assert_eq!(options.config.mode, GameMode::ThreePlayer);
+        assert_eq!(options.config.variant, RuleVariant::Pisti);
+        assert_eq!((options.seed, options.threads), (7, 2));
+
+        assert!(parse(&["--mode", "four"]).unwrap_err().contains("needs 4 agents"));
+        assert!(parse(&["--agents", "heuristic,grandmaster"]).unwrap_err().contains("Unknown agent"));
+        assert!(parse(&["--games"]).unwrap_err().contains("Missing value"));
+        assert!(parse(&["--games", "many"]).is_err());
+        assert!(parse(&["--threads", "0"]).is_err());
+        assert!(parse(&["--fast", "yes"]).unwrap_err().contains("Unknown option"));
+    }
+
+    #[test]
+    fn test_merge_stats() {
+        let mut total = Stats::new(2);
+        let mut batch = Stats::new(2);
+        batch.games = 3;
+        batch.wins = vec![2, 0];
+        batch.draws = 1;
+        batch.scores = vec![40, 25];
+        batch.kseris = vec![1, 2];
+        batch.majority_decided = 1;
+
+        total.merge(batch);
+        let mut other = Stats::new(2);
+        other.games = 1;
+        other.wins = vec![0, 1];
+        other.scores = vec![5, 20];
+        other.last_capture_decided = 1;
+        total.merge(other);
+
+        assert_eq!(total.games, 4);
+        assert_eq!(total.wins, vec![2, 1]);
+        assert_eq!(total.draws, 1);
+        assert_eq!(total.scores, vec![45, 45]);
+        assert_eq!(total.kseris, vec![1, 2]);
+        assert_eq!((total.majority_decided, total.last_capture_decided), (1, 1));
+    }
+
+    #[test]
+    fn test_win_rate_interval() {
+        let (rate, margin) = win_rate(50, 100);
+        assert_eq!(rate, 0.5);
+        assert!((margin - 0.098).abs() < 0.001);
+
+        // Certain results and empty runs have no spread
+        assert_eq!(win_rate(10, 10), (1.0, 0.0));
+        assert_eq!(win_rate(0, 0), (0.0, 0.0));
+        // More games narrow the interval
+        assert!(win_rate(500, 1000).1 < margin);
+    }
+
+    #[test]
+    fn test_seed_reproduces_a_run() {
+        let options = parse(&["--agents", "easy,random"]).unwrap();
+        let run = |seed: u64| {
+            let mut stats = Stats::new(2);
+            for game in 0..3 {
+                play_game(&options, seed.wrapping_add(game), &mut stats);
+            }
+            stats
+        };
+        assert_eq!(run(11), run(11));
+        // Seeds near the top of the range wrap instead of overflowing
+        assert_eq!(run(u64::MAX), run(u64::MAX));
+    }
+}
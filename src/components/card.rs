@@ -107,17 +107,60 @@ impl fmt::Display for Rank {
     }
 }
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Card {
     pub suit: Suit,
     pub rank: Rank,
 }
 
+/// How a card looks in JSON and other human-readable formats
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Card")]
+struct CardFields {
+    suit: Suit,
+    rank: Rank,
+}
+
+/// Cards are `{"suit":"Hearts","rank":"Ace"}` in human-readable formats
+/// and a single `Card::index` byte in binary ones
+impl Serialize for Card {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            CardFields { suit: self.suit, rank: self.rank }.serialize(serializer)
+        } else {
+            serializer.serialize_u8(self.index())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let fields = CardFields::deserialize(deserializer)?;
+            Ok(Card::new(fields.suit, fields.rank))
+        } else {
+            let index = u8::deserialize(deserializer)?;
+            Card::from_index(index)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid card index {}", index)))
+        }
+    }
+}
+
 impl Card {
     pub fn new(suit: Suit, rank: Rank) -> Self {
         Card { suit, rank }
     }
     
+    /// Position in a suit-by-suit deck, 0 (Ace of Hearts) to 51 (King of Spades)
+    pub fn index(&self) -> u8 {
+        self.suit as u8 * 13 + self.rank.value() - 1
+    }
+    
+    pub fn from_index(index: u8) -> Option<Self> {
+        let suit = *Suit::all().get(index as usize / 13)?;
+        Some(Card::new(suit, Rank::all()[index as usize % 13]))
+    }
+    
     /// Check if this card can capture the given card in Kseri
     pub fn can_capture(&self, other: &Card) -> bool {
         // In Kseri, cards capture by matching rank
@@ -169,6 +212,18 @@ pub struct PlayerId(pub u8);
 impl PlayerId {
     pub const PLAYER_ONE: PlayerId = PlayerId(0);
     pub const PLAYER_TWO: PlayerId = PlayerId(1);
+    pub const PLAYER_THREE: PlayerId = PlayerId(2);
+    pub const PLAYER_FOUR: PlayerId = PlayerId(3);
+    
+    /// Seat index, usable for per-seat arrays
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+    
+    /// The seat that plays after this one at a table of `seat_count` players
+    pub fn next(&self, seat_count: usize) -> PlayerId {
+        PlayerId(((self.index() + 1) % seat_count) as u8)
+    }
 }
 
 impl Default for PlayerId {
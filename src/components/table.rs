@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use crate::components::card::{Card, Suit, Rank};
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
 
 #[derive(Component, Default)]
 pub struct TablePile {
@@ -113,7 +113,12 @@ impl Deck {
     /// Shuffle the deck using Fisher-Yates algorithm
     pub fn shuffle(&mut self) {
         let mut rng = thread_rng();
-        self.cards.shuffle(&mut rng);
+        self.shuffle_with(&mut rng);
+    }
+    
+    /// Shuffle the deck with a caller-provided RNG (use a seeded RNG for reproducible deals)
+    pub fn shuffle_with<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
     }
     
     /// Draw a card from the top of the deck
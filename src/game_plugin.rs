@@ -60,6 +60,33 @@ impl Plugin for KseriGamePlugin {
             OnEnter(GameState::GameOver),
             calculate_final_scores
         );
+        
+        // Replay viewer, only available when a record was opened
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            app.add_systems(Startup, start_replay.run_if(resource_exists::<ReplayViewer>));
+            app.add_systems(OnEnter(GameState::Replay), setup_replay);
+            app.add_systems(OnExit(GameState::Replay), cleanup_replay);
+            app.add_systems(
+                Update,
+                (
+                    replay_input,
+                    replay_autoplay,
+                    sync_replay_cards,
+                    update_replay_table,
+                    update_replay_status,
+                ).chain().run_if(in_state(GameState::Replay).and(resource_exists::<ReplayViewer>))
+            );
+            
+            // Chat panel while playing
+            app.init_resource::<ChatPanel>();
+            app.add_systems(OnEnter(GameState::Playing), setup_chat_panel);
+            app.add_systems(OnExit(GameState::Playing), cleanup_chat_panel);
+            app.add_systems(
+                Update,
+                (chat_input, update_chat_panel).chain().run_if(in_state(GameState::Playing))
+            );
+        }
     }
 }
 
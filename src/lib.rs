@@ -48,6 +48,19 @@ pub fn run() {
     
     let mut app = App::new();
     
+    // `--replay <file>` opens a game record saved by the server instead of the menu
+    #[cfg(not(target_arch = "wasm32"))]
+    let replay = std::env::args()
+        .skip_while(|arg| arg != "--replay")
+        .nth(1)
+        .and_then(|path| match crate::systems::replay::ReplayViewer::load(&path) {
+            Ok(viewer) => Some(viewer),
+            Err(e) => {
+                eprintln!("Cannot open replay: {}", e);
+                None
+            }
+        });
+    
     app.add_plugins(DefaultPlugins.set(WindowPlugin {
         primary_window: Some(Window {
             title: "Kseri".to_string(),
@@ -68,6 +81,15 @@ pub fn run() {
         player_name: "Arkid".to_string(),
         opponent_name: "Sofia".to_string(),
     });
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(viewer) = replay {
+        // Label the seats with the recorded names
+        app.insert_resource(GameSettings {
+            player_name: viewer.record.players[0].clone(),
+            opponent_name: viewer.record.players.get(1).cloned().unwrap_or_default(),
+        });
+        app.insert_resource(viewer);
+    }
     app.insert_resource(NetworkState::default());
     app.insert_resource(TurnManager::default());
     
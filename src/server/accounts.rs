@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// How long a login token stays valid
+pub const TOKEN_LIFETIME: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+pub const MIN_PASSWORD_LEN: usize = 8;
+pub const USERNAME_LEN: std::ops::RangeInclusive<usize> = 3..=20;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A registered player. Only a salted hash of the password is kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub username: String,
+    pub password_hash: String,
+    /// Stable identity used for ratings, the same on every device
+    pub identity: String,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+}
+
+/// What a successful register or login hands back to the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Login {
+    pub username: String,
+    pub identity: String,
+    /// Signed token to log in with next time instead of the password
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TokenClaims {
+    username: String,
+    /// Expiry in seconds since the Unix epoch
+    expires: u64,
+}
+
+/// Everything written to the accounts file
+#[derive(Default, Serialize, Deserialize)]
+struct AccountData {
+    /// Key tokens are signed with, created with the file
+    secret: String,
+    /// Accounts by lowercased username
+    accounts: HashMap<String, Account>,
+}
+
+/// Player accounts in a local JSON file, or in memory without a path
+pub struct AccountStore {
+    path: Option<PathBuf>,
+    secret: Vec<u8>,
+    data: RwLock<AccountData>,
+}
+
+impl Default for AccountStore {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+fn new_secret() -> String {
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    URL_SAFE_NO_PAD.encode(secret)
+}
+
+/// Check a username before it is registered: letters, digits, `_` and `-`
+pub fn validate_username(username: &str) -> Result<(), String> {
+    if !USERNAME_LEN.contains(&username.chars().count()) {
+        return Err(format!("Username must be {} to {} characters", USERNAME_LEN.start(), USERNAME_LEN.end()));
+    }
+    if !username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err("Username may only contain letters, digits, '_' and '-'".to_string());
+    }
+    Ok(())
+}
+
+impl AccountStore {
+    /// Accounts that only live as long as the process
+    pub fn in_memory() -> Self {
+        let data = AccountData { secret: new_secret(), accounts: HashMap::new() };
+        Self::from_data(None, data)
+    }
+
+    /// Load the accounts saved at `path`, creating the file with a fresh signing key if needed
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                let data: AccountData = serde_json::from_slice(&bytes)?;
+                Ok(Self::from_data(Some(path), data))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let data = AccountData { secret: new_secret(), accounts: HashMap::new() };
+                write_new(&path, &serde_json::to_vec(&data)?)?;
+                Ok(Self::from_data(Some(path), data))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn from_data(path: Option<PathBuf>, data: AccountData) -> Self {
+        Self {
+            path,
+            secret: data.secret.as_bytes().to_vec(),
+            data: RwLock::new(data),
+        }
+    }
+
+    pub async fn len(&self) -> usize {
+        self.data.read().await.accounts.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// Create an account and log it in
+    pub async fn register(&self, username: &str, password: &str) -> Result<Login, String> {
+        validate_username(username)?;
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(format!("Password must be at least {} characters", MIN_PASSWORD_LEN));
+        }
+        if self.data.read().await.accounts.contains_key(&username.to_lowercase()) {
+            return Err("Username is taken".to_string());
+        }
+
+        let password = password.to_string();
+        let password_hash = tokio::task::spawn_blocking(move || {
+            let salt = SaltString::generate(&mut OsRng);
+            Argon2::default().hash_password(password.as_bytes(), &salt).map(|hash| hash.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+        let account = Account {
+            username: username.to_string(),
+            password_hash,
+            identity: Uuid::new_v4().to_string(),
+            created_at: now_secs(),
+        };
+
+        let mut data = self.data.write().await;
+        if data.accounts.contains_key(&username.to_lowercase()) {
+            return Err("Username is taken".to_string());
+        }
+        // Saved under the same guard as the insert, so a later registration cannot be
+        // written before this one
+        data.accounts.insert(username.to_lowercase(), account.clone());
+        let saved = match serde_json::to_vec(&*data) {
+            Ok(json) => self.save(json).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = saved {
+            data.accounts.remove(&username.to_lowercase());
+            return Err(e);
+        }
+        drop(data);
+
+        Ok(self.issue(&account))
+    }
+
+    /// Log in with a password; the error does not tell which part was wrong
+    pub async fn login(&self, username: &str, password: &str) -> Result<Login, String> {
+        const INVALID: &str = "Wrong username or password";
+
+        let account = self.data.read().await.accounts.get(&username.to_lowercase()).cloned()
+            .ok_or(INVALID)?;
+
+        let password = password.to_string();
+        let hash = account.password_hash.clone();
+        let verified = tokio::task::spawn_blocking(move || {
+            PasswordHash::new(&hash)
+                .map(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+                .unwrap_or(false)
+        })
+        .await
+        .unwrap_or(false);
+
+        if !verified {
+            return Err(INVALID.to_string());
+        }
+        Ok(self.issue(&account))
+    }
+
+    /// Log in with a token from an earlier login; a fresh token is issued
+    pub async fn login_with_token(&self, token: &str) -> Result<Login, String> {
+        const INVALID: &str = "Invalid or expired login token";
+
+        let (payload, signature) = token.split_once('.').ok_or(INVALID)?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| INVALID)?;
+        let mut mac = HmacSha256::new_from_slice(&self.secret).map_err(|_| INVALID)?;
+        mac.update(payload.as_bytes());
+        mac.verify_slice(&signature).map_err(|_| INVALID)?;
+
+        let claims: TokenClaims = URL_SAFE_NO_PAD.decode(payload).ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or(INVALID)?;
+        if claims.expires < now_secs() {
+            return Err(INVALID.to_string());
+        }
+
+        let account = self.data.read().await.accounts.get(&claims.username.to_lowercase()).cloned()
+            .ok_or(INVALID)?;
+        Ok(self.issue(&account))
+    }
+
+    /// Whether `identity` belongs to an account, so only a login may claim it
+    pub async fn owns_identity(&self, identity: &str) -> bool {
+        self.data.read().await.accounts.values().any(|account| account.identity == identity)
+    }
+
+    /// Sign a token for `account`: base64 claims, a dot, then their HMAC-SHA256
+    fn issue(&self, account: &Account) -> Login {
+        let claims = TokenClaims {
+            username: account.username.clone(),
+            expires: now_secs() + TOKEN_LIFETIME.as_secs(),
+        };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap_or_default());
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(payload.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+        Login {
+            username: account.username.clone(),
+            identity: account.identity.clone(),
+            token: format!("{}.{}", payload, signature),
+        }
+    }
+
+    /// Replace the accounts file atomically, like `SessionStore::save`
+    async fn save(&self, json: Vec<u8>) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let tmp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        file.write_all(&json).await?;
+        file.sync_all().await?;
+        drop(file);
+
+        if let Err(e) = tokio::fs::rename(&tmp, path).await {
+            tokio::fs::remove_file(&tmp).await.ok();
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+/// Write the first accounts file through a temporary file like `save`, so a crash
+/// cannot leave a truncated signing key behind
+fn write_new(path: &Path, json: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(json)?;
+    file.sync_all()?;
+    drop(file);
+
+    if let Err(e) = std::fs::rename(&tmp, path) {
+        std::fs::remove_file(&tmp).ok();
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_register_and_login() {
+        let path = std::env::temp_dir().join(format!("kseri-accounts-{}.json", Uuid::new_v4()));
+        let store = AccountStore::open(&path).unwrap();
+
+        assert!(store.register("ab", "long enough").await.is_err());
+        assert!(store.register("has space", "long enough").await.is_err());
+        assert!(store.register("alice", "short").await.is_err());
+        let registered = store.register("Alice", "correct horse").await.unwrap();
+        assert!(store.register("alice", "another one").await.is_err());
+
+        // Reopening keeps the account and the signing key, so old tokens still work
+        let store = AccountStore::open(&path).unwrap();
+        assert_eq!(store.len().await, 1);
+        let login = store.login("ALICE", "correct horse").await.unwrap();
+        assert_eq!(login.identity, registered.identity);
+        assert_eq!(login.username, "Alice");
+        assert!(store.login("alice", "wrong horse").await.is_err());
+        assert!(store.login("bob", "correct horse").await.is_err());
+
+        assert_eq!(store.login_with_token(&registered.token).await.unwrap().identity, registered.identity);
+        assert!(store.owns_identity(&registered.identity).await);
+        assert!(!store.owns_identity("made-up").await);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_registrations_are_all_saved() {
+        let path = std::env::temp_dir().join(format!("kseri-accounts-{}.json", Uuid::new_v4()));
+        let store = AccountStore::open(&path).unwrap();
+        let names: Vec<String> = (0..4).map(|n| format!("player{}", n)).collect();
+
+        let registered = futures_util::future::join_all(names.iter().map(|name| store.register(name, "correct horse"))).await;
+        assert!(registered.iter().all(|login| login.is_ok()));
+
+        let reopened = AccountStore::open(&path).unwrap();
+        assert_eq!(reopened.len().await, names.len());
+        for name in &names {
+            assert!(reopened.login(name, "correct horse").await.is_ok());
+        }
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_forged_tokens_are_rejected() {
+        let store = AccountStore::in_memory();
+        let login = store.register("alice", "correct horse").await.unwrap();
+        store.register("mallory", "correct horse").await.unwrap();
+
+        // Claims swapped for another user without re-signing
+        let (_, signature) = login.token.split_once('.').unwrap();
+        let claims = TokenClaims { username: "mallory".to_string(), expires: u64::MAX };
+        let forged = format!("{}.{}", URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap()), signature);
+        assert!(store.login_with_token(&forged).await.is_err());
+        assert!(store.login_with_token("garbage").await.is_err());
+
+        // Tokens from another server's key do not work here
+        let other = AccountStore::in_memory();
+        assert!(other.login_with_token(&login.token).await.is_err());
+    }
+}
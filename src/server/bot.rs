@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::card::{Card, Rank};
+use crate::server::game_engine::PlayerView;
+use crate::server::rules::RuleSet;
+use crate::server::search::{Difficulty, SearchBot};
+
+/// Cost of spending a Jack, roughly what holding on to it is worth later in the deal
+const JACK_COST: f32 = 2.5;
+/// Worth of each captured card towards the majority bonus
+const CARD_WEIGHT: f32 = 0.2;
+/// Cards dealt to a seat that has run out, matching the engine
+const HAND_SIZE: usize = 4;
+
+/// Chooses plays for a seat using only what that seat can see
+pub trait Bot: Send + Sync {
+    /// Card to play from `view.hand`, or `None` when the hand is empty
+    fn choose_card(&mut self, view: &PlayerView) -> Option<Card>;
+}
+
+/// Bots a player can ask to play against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum BotKind {
+    /// Greedy one-move lookahead: captures, kseris and safe discards
+    #[default]
+    Heuristic,
+    /// Information-set Monte Carlo search with the budget of the given difficulty
+    Search(Difficulty),
+}
+
+impl BotKind {
+    pub fn create(&self) -> Box<dyn Bot> {
+        match self {
+            BotKind::Heuristic => Box::new(HeuristicBot),
+            BotKind::Search(difficulty) => Box::new(SearchBot::new(difficulty.budget())),
+        }
+    }
+
+    /// Name shown to the other players at the table
+    pub fn name(&self) -> &'static str {
+        match self {
+            BotKind::Heuristic => "Bot",
+            BotKind::Search(Difficulty::Easy) => "Easy Bot",
+            BotKind::Search(Difficulty::Medium) => "Medium Bot",
+            BotKind::Search(Difficulty::Hard) => "Hard Bot",
+            BotKind::Search(Difficulty::Expert) => "Expert Bot",
+        }
+    }
+}
+
+/// Scores every card in hand by the points it takes now, minus what it leaves
+/// for the next seat and the value of any Jack spent
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicBot;
+
+impl HeuristicBot {
+    /// Expected points for playing `card`; higher is better
+    pub fn evaluate(view: &PlayerView, card: Card) -> f32 {
+        let rules = view.config.variant.rules();
+        let unseen = view.unseen_cards();
+
+        // Face-down cards are valued at the average of everything still unseen
+        let hidden_value = if unseen.is_empty() {
+            0.0
+        } else {
+            unseen.iter().map(|c| rules.card_value(c) as f32).sum::<f32>() / unseen.len() as f32
+        };
+        let pile_size = view.table_cards.len() + view.table_hidden;
+        let pile_value = view.table_cards.iter().map(|c| rules.card_value(c) as f32).sum::<f32>()
+            + hidden_value * view.table_hidden as f32
+            + CARD_WEIGHT * pile_size as f32;
+        let card_value = rules.card_value(&card) as f32 + CARD_WEIGHT;
+        let jack_cost = if card.rank == Rank::Jack { JACK_COST } else { 0.0 };
+
+        let captures = view.table_cards.last()
+            .map(|top| card.rank == Rank::Jack || top.rank == card.rank)
+            .unwrap_or(false);
+
+        if captures {
+            pile_value + card_value + kseri_bonus(&rules, &view.table_cards, pile_size, card) - jack_cost
+        } else {
+            // The card joins the pile; the next seat takes it all with the same rank or a Jack
+            let mut at_risk = pile_value + card_value;
+            let mut matching = unseen.iter().filter(|c| c.rank == card.rank).count();
+            if pile_size == 0 {
+                // A lone card on an empty table is a kseri waiting to happen
+                at_risk += kseri_bonus(&rules, &[card], 1, card);
+            } else if card.rank != Rank::Jack {
+                matching += unseen.iter().filter(|c| c.rank == Rank::Jack).count();
+            }
+
+            -at_risk * capture_chance(view, unseen.len(), matching) - jack_cost
+        }
+    }
+}
+
+impl Bot for HeuristicBot {
+    fn choose_card(&mut self, view: &PlayerView) -> Option<Card> {
+        let mut best: Option<(Card, f32)> = None;
+
+        for &card in &view.hand {
+            let score = Self::evaluate(view, card);
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((card, score));
+            }
+        }
+
+        best.map(|(card, _)| card)
+    }
+}
+
+/// Bonus for taking a pile of `pile_size` whose face-up part is `table` with `card`
+fn kseri_bonus(rules: &RuleSet, table: &[Card], pile_size: usize, card: Card) -> f32 {
+    match table.last() {
+        Some(top) if pile_size == 1 && top.rank == card.rank => {
+            let bonus = if card.rank == Rank::Jack { rules.jack_kseri_bonus.unwrap_or(0) } else { rules.kseri_bonus };
+            bonus as f32
+        }
+        _ => 0.0,
+    }
+}
+
+/// Chance that the next seat holds at least one of `matching` cards among the `unseen`
+fn capture_chance(view: &PlayerView, unseen: usize, matching: usize) -> f32 {
+    let next = view.seat.next(view.hand_counts.len());
+    let mut drawn = view.hand_counts[next.index()];
+    if drawn == 0 && view.deck_remaining > 0 {
+        drawn = HAND_SIZE;
+    }
+
+    // Hypergeometric: probability that none of the `drawn` unseen cards match
+    let mut miss = 1.0;
+    for i in 0..drawn.min(unseen) {
+        let remaining = (unseen - i) as f32;
+        miss *= (remaining - matching as f32).max(0.0) / remaining;
+    }
+
+    1.0 - miss
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::card::{PlayerId, Suit};
+    use crate::server::game_engine::KseriGameState;
+    use crate::server::rules::{GameConfig, GameMode};
+
+    fn card(suit: Suit, rank: Rank) -> Card {
+        Card::new(suit, rank)
+    }
+
+    fn view(table: &[Card], hand: &[Card], captured: &[Card]) -> PlayerView {
+        PlayerView {
+            seat: PlayerId::PLAYER_ONE,
+            config: GameConfig::default(),
+            hand: hand.to_vec(),
+            table_cards: table.to_vec(),
+            table_hidden: 0,
+            hand_counts: vec![hand.len(), hand.len()],
+            deck_remaining: 20,
+            captured: vec![captured.to_vec(), Vec::new()],
+            captured_hidden: vec![0, 0],
+            kseri_count: vec![0, 0],
+            jack_kseri_count: vec![0, 0],
+            current_turn: PlayerId::PLAYER_ONE,
+            last_capture_player: None,
+        }
+    }
+
+    #[test]
+    fn test_goes_for_kseri() {
+        let table = [card(Suit::Spades, Rank::Nine)];
+        let hand = [card(Suit::Hearts, Rank::Jack), card(Suit::Clubs, Rank::Nine), card(Suit::Hearts, Rank::Four)];
+
+        assert_eq!(HeuristicBot.choose_card(&view(&table, &hand, &[])), Some(hand[1]));
+    }
+
+    #[test]
+    fn test_saves_jack_for_a_better_pile() {
+        let table = [card(Suit::Spades, Rank::Nine), card(Suit::Hearts, Rank::Five)];
+        let hand = [card(Suit::Hearts, Rank::Jack), card(Suit::Clubs, Rank::Five), card(Suit::Hearts, Rank::Four)];
+
+        // The Five takes the same pile without spending the Jack
+        assert_eq!(HeuristicBot.choose_card(&view(&table, &hand, &[])), Some(hand[1]));
+    }
+
+    #[test]
+    fn test_avoids_setting_up_a_kseri() {
+        // Every other Seven has been seen, so it is the only safe card to lead
+        let sevens = [card(Suit::Hearts, Rank::Seven), card(Suit::Diamonds, Rank::Seven), card(Suit::Clubs, Rank::Seven)];
+        let hand = [card(Suit::Spades, Rank::King), card(Suit::Spades, Rank::Seven)];
+
+        assert_eq!(HeuristicBot.choose_card(&view(&[], &hand, &sevens)), Some(hand[1]));
+    }
+
+    #[test]
+    fn test_bots_play_out_every_mode() {
+        for mode in GameMode::all() {
+            let config = GameConfig { mode, ..Default::default() };
+            let mut state = KseriGameState::with_config(3, config);
+
+            while !state.game_over {
+                let seat = state.current_turn;
+                let card = HeuristicBot.choose_card(&state.player_view(seat)).unwrap();
+                state.play_card(seat, card).unwrap();
+            }
+            assert!(state.validate_state().is_ok());
+        }
+    }
+}
@@ -182,8 +182,8 @@ impl Broadcaster {
                 
                 BroadcastTarget::Session(session_id) => {
                     // Get all connections in session
-                    if let Some(session) = session_manager.sessions.get(&session_id) {
-                        for player in session.players.iter().filter_map(|p| p.as_ref()) {
+                    if let Some(session) = session_manager.session(&session_id) {
+                        for player in session.players().iter().filter_map(|p| p.as_ref()) {
                             if player.connected {
                                 by_connection.entry(player.connection_id)
                                     .or_insert_with(Vec::new)
@@ -203,11 +203,9 @@ impl Broadcaster {
         
         // Send messages to connections
         for (conn_id, messages) in by_connection {
-            if let Some(conn) = connection_manager.get_connection(&conn_id) {
-                for msg in messages {
-                    if let Err(e) = conn.send(msg).await {
-                        debug!("Failed to send message to {}: {}", conn_id, e);
-                    }
+            for msg in messages {
+                if let Err(e) = connection_manager.send(&conn_id, msg) {
+                    debug!("Failed to send message to {}: {}", conn_id, e);
                 }
             }
         }
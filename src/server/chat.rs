@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::card::PlayerId;
+
+/// Longest chat line accepted, in characters
+pub const MAX_CHAT_LEN: usize = 200;
+/// Chat lines and emotes a player may send within `CHAT_WINDOW`
+pub const CHAT_BURST: usize = 5;
+pub const CHAT_WINDOW: Duration = Duration::from_secs(10);
+
+/// Quick reactions that can be sent with a single key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Emote {
+    NiceKseri,
+    GoodGame,
+    WellPlayed,
+    Oops,
+    Thanks,
+}
+
+impl Emote {
+    pub fn all() -> [Emote; 5] {
+        [Emote::NiceKseri, Emote::GoodGame, Emote::WellPlayed, Emote::Oops, Emote::Thanks]
+    }
+
+    pub fn text(&self) -> &'static str {
+        match self {
+            Emote::NiceKseri => "Nice kseri!",
+            Emote::GoodGame => "Good game",
+            Emote::WellPlayed => "Well played",
+            Emote::Oops => "Oops",
+            Emote::Thanks => "Thanks!",
+        }
+    }
+}
+
+/// Trim a chat line and check it can be sent: not empty, not too long and free of control characters
+pub fn clean_chat(text: &str) -> Result<String, String> {
+    let text: String = text.trim().chars().filter(|c| !c.is_control()).collect();
+    if text.is_empty() {
+        return Err("Message is empty".to_string());
+    }
+    if text.chars().count() > MAX_CHAT_LEN {
+        return Err(format!("Message is longer than {} characters", MAX_CHAT_LEN));
+    }
+    Ok(text)
+}
+
+/// Rate limits and mutes for the chat at one table
+#[derive(Debug, Clone)]
+pub struct ChatState {
+    /// When each seat last spoke, oldest first, within `CHAT_WINDOW`
+    sent: Vec<VecDeque<Instant>>,
+    /// Seats each seat has muted
+    muted: Vec<Vec<PlayerId>>,
+}
+
+impl ChatState {
+    pub fn new(seat_count: usize) -> Self {
+        Self {
+            sent: vec![VecDeque::new(); seat_count],
+            muted: vec![Vec::new(); seat_count],
+        }
+    }
+
+    /// Count a message from `seat`, or refuse it when the seat is over its limit
+    pub fn allow(&mut self, seat: PlayerId) -> bool {
+        let sent = &mut self.sent[seat.index()];
+        while sent.front().is_some_and(|at| at.elapsed() >= CHAT_WINDOW) {
+            sent.pop_front();
+        }
+        if sent.len() >= CHAT_BURST {
+            return false;
+        }
+        sent.push_back(Instant::now());
+        true
+    }
+
+    pub fn set_muted(&mut self, listener: PlayerId, speaker: PlayerId, muted: bool) {
+        let list = &mut self.muted[listener.index()];
+        list.retain(|seat| *seat != speaker);
+        if muted {
+            list.push(speaker);
+        }
+    }
+
+    pub fn is_muted(&self, listener: PlayerId, speaker: PlayerId) -> bool {
+        self.muted[listener.index()].contains(&speaker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_chat() {
+        assert_eq!(clean_chat("  hi\u{7}  there \n").unwrap(), "hi  there");
+        assert!(clean_chat("   ").is_err());
+        assert!(clean_chat(&"ş".repeat(MAX_CHAT_LEN)).is_ok());
+        assert!(clean_chat(&"a".repeat(MAX_CHAT_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_rate_limit_and_mute() {
+        let mut chat = ChatState::new(2);
+        for _ in 0..CHAT_BURST {
+            assert!(chat.allow(PlayerId::PLAYER_ONE));
+        }
+        assert!(!chat.allow(PlayerId::PLAYER_ONE));
+        // Limits are per seat
+        assert!(chat.allow(PlayerId::PLAYER_TWO));
+
+        chat.set_muted(PlayerId::PLAYER_TWO, PlayerId::PLAYER_ONE, true);
+        assert!(chat.is_muted(PlayerId::PLAYER_TWO, PlayerId::PLAYER_ONE));
+        assert!(!chat.is_muted(PlayerId::PLAYER_ONE, PlayerId::PLAYER_TWO));
+        chat.set_muted(PlayerId::PLAYER_TWO, PlayerId::PLAYER_ONE, false);
+        assert!(!chat.is_muted(PlayerId::PLAYER_TWO, PlayerId::PLAYER_ONE));
+    }
+}
@@ -0,0 +1,191 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::card::{Card, PlayerId};
+use crate::server::game_engine::KseriGameState;
+
+/// What the server does for a player whose turn runs out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AutoPlay {
+    /// Throw away the card worth the fewest points
+    #[default]
+    LowestCard,
+    /// Let the default bot choose
+    Bot,
+}
+
+/// Time limits applied to every human seat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockConfig {
+    /// Time for a single move
+    pub per_move: Duration,
+    /// Time bank for the whole game, spent by every move; `None` for no limit
+    pub per_game: Option<Duration>,
+    /// Warn the player when this much time is left
+    pub warning: Duration,
+    /// Consecutive timeouts after which the player forfeits
+    pub max_timeouts: u32,
+    pub auto_play: AutoPlay,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            per_move: Duration::from_secs(30),
+            per_game: None,
+            warning: Duration::from_secs(10),
+            max_timeouts: 3,
+            auto_play: AutoPlay::default(),
+        }
+    }
+}
+
+/// What `TurnClocks::check` found for the seat to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockCheck {
+    Running,
+    /// Crossed the warning threshold, reported once per turn
+    Warning(Duration),
+    /// Out of time; holds the seat's consecutive timeouts including this one
+    Expired(u32),
+}
+
+/// Clocks of one table: the running turn and each seat's time bank
+#[derive(Debug, Clone)]
+pub struct TurnClocks {
+    pub config: ClockConfig,
+    turn_started: Instant,
+    /// Game time left per seat, empty without a game clock
+    banks: Vec<Duration>,
+    /// Consecutive timeouts per seat
+    timeouts: Vec<u32>,
+    warned: bool,
+}
+
+impl TurnClocks {
+    pub fn new(config: ClockConfig, seat_count: usize) -> Self {
+        Self {
+            config,
+            turn_started: Instant::now(),
+            banks: config.per_game.map(|bank| vec![bank; seat_count]).unwrap_or_default(),
+            timeouts: vec![0; seat_count],
+            warned: false,
+        }
+    }
+
+    /// Charge the time `seat` took to its bank and start the next turn
+    pub fn finish_turn(&mut self, seat: PlayerId) {
+        let elapsed = self.turn_started.elapsed();
+        if let Some(bank) = self.banks.get_mut(seat.index()) {
+            *bank = bank.saturating_sub(elapsed);
+        }
+        self.turn_started = Instant::now();
+        self.warned = false;
+    }
+
+    /// The player moved on their own, so their timeouts are no longer consecutive
+    pub fn reset_timeouts(&mut self, seat: PlayerId) {
+        self.timeouts[seat.index()] = 0;
+    }
+
+    /// Time `seat` has left for the running turn, whichever of its clocks runs out first
+    pub fn remaining(&self, seat: PlayerId) -> Duration {
+        let elapsed = self.turn_started.elapsed();
+        let turn = self.config.per_move.saturating_sub(elapsed);
+        match self.banks.get(seat.index()) {
+            Some(bank) => turn.min(bank.saturating_sub(elapsed)),
+            None => turn,
+        }
+    }
+
+    /// Game time left per seat, charged up to the start of the running turn
+    pub fn banks(&self) -> &[Duration] {
+        &self.banks
+    }
+
+    /// See whether `seat`, which is to move, should be warned or has run out of time
+    pub fn check(&mut self, seat: PlayerId) -> ClockCheck {
+        let remaining = self.remaining(seat);
+        if remaining.is_zero() {
+            self.timeouts[seat.index()] += 1;
+            ClockCheck::Expired(self.timeouts[seat.index()])
+        } else if remaining <= self.config.warning && !self.warned {
+            self.warned = true;
+            ClockCheck::Warning(remaining)
+        } else {
+            ClockCheck::Running
+        }
+    }
+}
+
+/// Card worth the fewest points in `seat`'s hand, lowest rank first on ties
+pub fn lowest_card(game_state: &KseriGameState, seat: PlayerId) -> Option<Card> {
+    let rules = game_state.config.variant.rules();
+    game_state.player_hands[seat.index()].iter()
+        .min_by_key(|card| (rules.card_value(card), card.rank.value()))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::card::{Rank, Suit};
+
+    fn config(per_move: u64, per_game: Option<u64>) -> ClockConfig {
+        ClockConfig {
+            per_move: Duration::from_millis(per_move),
+            per_game: per_game.map(Duration::from_millis),
+            warning: Duration::from_millis(per_move / 2),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_turn_clock_warns_then_expires() {
+        let mut clocks = TurnClocks::new(config(40, None), 2);
+        assert_eq!(clocks.check(PlayerId::PLAYER_ONE), ClockCheck::Running);
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(matches!(clocks.check(PlayerId::PLAYER_ONE), ClockCheck::Warning(_)));
+        // Only warned once per turn
+        assert_eq!(clocks.check(PlayerId::PLAYER_ONE), ClockCheck::Running);
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(clocks.check(PlayerId::PLAYER_ONE), ClockCheck::Expired(1));
+        clocks.finish_turn(PlayerId::PLAYER_ONE);
+        assert_eq!(clocks.check(PlayerId::PLAYER_TWO), ClockCheck::Running);
+
+        // Timeouts count up until the player moves themselves
+        std::thread::sleep(Duration::from_millis(45));
+        assert_eq!(clocks.check(PlayerId::PLAYER_ONE), ClockCheck::Expired(2));
+        clocks.reset_timeouts(PlayerId::PLAYER_ONE);
+        assert_eq!(clocks.check(PlayerId::PLAYER_ONE), ClockCheck::Expired(1));
+    }
+
+    #[test]
+    fn test_game_clock_is_spent_by_every_move() {
+        let mut clocks = TurnClocks::new(config(1000, Some(30)), 2);
+        std::thread::sleep(Duration::from_millis(20));
+        clocks.finish_turn(PlayerId::PLAYER_ONE);
+
+        // Only the mover's bank was charged, and it now limits their next turn
+        assert!(clocks.banks()[0] <= Duration::from_millis(10));
+        assert_eq!(clocks.banks()[1], Duration::from_millis(30));
+        assert!(clocks.remaining(PlayerId::PLAYER_ONE) <= Duration::from_millis(10));
+        assert!(clocks.remaining(PlayerId::PLAYER_TWO) > Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_lowest_card_keeps_points() {
+        let mut state = KseriGameState::with_seed(1);
+        state.player_hands[0] = vec![
+            Card::new(Suit::Diamonds, Rank::Ten),
+            Card::new(Suit::Hearts, Rank::Ace),
+            Card::new(Suit::Spades, Rank::King),
+            Card::new(Suit::Clubs, Rank::Four),
+        ];
+
+        assert_eq!(lowest_card(&state, PlayerId::PLAYER_ONE), Some(Card::new(Suit::Clubs, Rank::Four)));
+    }
+}
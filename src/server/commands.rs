@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::server::game_engine::PlayError;
+use crate::server::limits::Rejection;
+
+/// Outcomes remembered per seat and per connection for answering retries
+pub const COMMAND_MEMORY: usize = 64;
+
+/// Why a command was refused, sent in `Nack`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NackReason {
+    NotInGame,
+    NotYourTurn,
+    CardNotInHand,
+    /// Any other move the rules do not allow
+    InvalidMove { reason: String },
+    /// Refused by the abuse limits, e.g. an invalid name
+    Rejected(Rejection),
+    /// `Hello` and `Command` cannot be sent as commands
+    Unsupported,
+    Failed { message: String },
+}
+
+impl NackReason {
+    /// Reason for a failed move, typed when the engine said what was wrong
+    pub fn from_play_error(error: &(dyn std::error::Error + Send + Sync + 'static)) -> Self {
+        match error.downcast_ref::<PlayError>() {
+            Some(PlayError::NotYourTurn) => NackReason::NotYourTurn,
+            Some(PlayError::CardNotInHand) => NackReason::CardNotInHand,
+            Some(PlayError::GameOver) | None => NackReason::InvalidMove { reason: error.to_string() },
+        }
+    }
+}
+
+impl fmt::Display for NackReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NackReason::NotInGame => f.write_str("Not in a game session"),
+            NackReason::NotYourTurn => PlayError::NotYourTurn.fmt(f),
+            NackReason::CardNotInHand => PlayError::CardNotInHand.fmt(f),
+            NackReason::InvalidMove { reason } => f.write_str(reason),
+            NackReason::Rejected(rejection) => write!(f, "{:?}", rejection),
+            NackReason::Unsupported => f.write_str("Not allowed as a command"),
+            NackReason::Failed { message } => f.write_str(message),
+        }
+    }
+}
+
+pub type CommandOutcome = Result<(), NackReason>;
+
+/// Outcomes of the latest commands, so a retried command is answered again
+/// instead of being applied twice
+#[derive(Debug, Clone, Default)]
+pub struct CommandLog {
+    recent: VecDeque<(u64, CommandOutcome)>,
+}
+
+impl CommandLog {
+    pub fn outcome(&self, id: u64) -> Option<CommandOutcome> {
+        self.recent.iter().find(|(seen, _)| *seen == id).map(|(_, outcome)| outcome.clone())
+    }
+
+    pub fn record(&mut self, id: u64, outcome: CommandOutcome) {
+        if self.recent.len() == COMMAND_MEMORY {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((id, outcome));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_log_forgets_oldest() {
+        let mut log = CommandLog::default();
+        log.record(1, Ok(()));
+        log.record(2, Err(NackReason::NotYourTurn));
+        assert_eq!(log.outcome(1), Some(Ok(())));
+        assert_eq!(log.outcome(2), Some(Err(NackReason::NotYourTurn)));
+        assert_eq!(log.outcome(3), None);
+
+        for id in 3..=COMMAND_MEMORY as u64 + 1 {
+            log.record(id, Ok(()));
+        }
+        assert_eq!(log.outcome(1), None);
+        assert!(log.outcome(2).is_some());
+    }
+
+    #[test]
+    fn test_play_errors_are_typed() {
+        let error: Box<dyn std::error::Error + Send + Sync> = PlayError::CardNotInHand.into();
+        assert_eq!(NackReason::from_play_error(error.as_ref()), NackReason::CardNotInHand);
+        let error: Box<dyn std::error::Error + Send + Sync> = "Session not found".into();
+        assert_eq!(NackReason::from_play_error(error.as_ref()).to_string(), "Session not found");
+    }
+}
@@ -1,3 +1,4 @@
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use dashmap::DashMap;
@@ -9,15 +10,31 @@ use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+use crate::components::card::PlayerId;
+use crate::server::accounts::Login;
+use crate::server::bot::BotKind;
+use crate::server::chat::Emote;
+use crate::server::commands::{CommandLog, NackReason};
+use crate::server::limits::{validate_player_name, ConnectionLimits, Rejection, TokenBucket};
 use crate::server::messages::{ClientMessage, ServerMessage};
+use crate::server::protocol::{check_version, Capability, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+use crate::server::rules::GameConfig;
+use crate::server::wire::{self, WireFormat};
 
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_LEADERBOARD: usize = 20;
 
 pub struct Connection {
     pub id: Uuid,
     pub session_id: Option<Uuid>,
     pub player_name: Option<String>,
+    /// Rated identity from `Identify`
+    pub identity: Option<String>,
+    /// Account the connection logged into; guests have none
+    pub username: Option<String>,
+    /// The client listed the `resume` capability and gets `Sequenced` messages
+    pub resume: bool,
     pub last_ping: Instant,
     tx: mpsc::UnboundedSender<ServerMessage>,
 }
@@ -28,12 +45,26 @@ impl Connection {
             id,
             session_id: None,
             player_name: None,
+            identity: None,
+            username: None,
+            resume: false,
             last_ping: Instant::now(),
             tx,
         }
     }
 
+    /// Queue a message for the client; numbered messages lose their envelope
+    /// unless the client asked for them
     pub async fn send(&self, message: ServerMessage) -> Result<(), &'static str> {
+        self.queue(message)
+    }
+
+    /// `send` without the await, for callers holding the connection's map entry
+    fn queue(&self, message: ServerMessage) -> Result<(), &'static str> {
+        let message = match message {
+            ServerMessage::Sequenced { message, .. } if !self.resume => *message,
+            message => message,
+        };
         self.tx.send(message).map_err(|_| "Failed to send message")
     }
 
@@ -73,6 +104,13 @@ impl ConnectionManager {
         self.connections.get_mut(id)
     }
 
+    /// Queue a message for a connection; the map entry is released before returning,
+    /// so callers never hold it across an await
+    pub fn send(&self, id: &Uuid, message: ServerMessage) -> Result<(), &'static str> {
+        let conn = self.connections.get(id).ok_or("Connection not found")?;
+        conn.queue(message)
+    }
+
     pub fn cleanup_timed_out(&self) -> Vec<Uuid> {
         let mut timed_out = Vec::new();
         
@@ -99,6 +137,21 @@ pub struct ConnectionHandler {
     rx: mpsc::UnboundedReceiver<ServerMessage>,
     manager: Arc<ConnectionManager>,
     sessions: Arc<crate::server::session::SessionManager>,
+    limits: Arc<ConnectionLimits>,
+    /// Address the connection came from, banned after too many malformed messages
+    ip: Option<IpAddr>,
+    bucket: TokenBucket,
+    malformed: u32,
+    /// Encoding of the messages sent to this client, see `ClientMessage::SetWireFormat`
+    wire_format: WireFormat,
+    /// Protocol version from the client's `Hello`; nothing else is handled before it
+    protocol_version: Option<u32>,
+    /// Outcomes of commands sent before the connection had a seat, see `ClientMessage::Command`
+    commands: CommandLog,
+    /// A `Command` is running; failures become its `Nack` instead of being sent
+    in_command: bool,
+    /// Why the running command failed
+    failure: Option<NackReason>,
 }
 
 impl ConnectionHandler {
@@ -109,15 +162,33 @@ impl ConnectionHandler {
         manager: Arc<ConnectionManager>,
         sessions: Arc<crate::server::session::SessionManager>,
     ) -> Self {
+        let limits = Arc::new(ConnectionLimits::default());
         Self {
             id,
             ws_stream,
             rx,
             manager,
             sessions,
+            bucket: limits.bucket(),
+            limits,
+            ip: None,
+            malformed: 0,
+            wire_format: WireFormat::Json,
+            protocol_version: None,
+            commands: CommandLog::default(),
+            in_command: false,
+            failure: None,
         }
     }
 
+    /// Apply `limits` instead of the defaults to this connection from `ip`
+    pub fn with_limits(mut self, limits: Arc<ConnectionLimits>, ip: IpAddr) -> Self {
+        self.bucket = limits.bucket();
+        self.limits = limits;
+        self.ip = Some(ip);
+        self
+    }
+
     pub async fn run(mut self) {
         info!("Connection {} established", self.id);
         
@@ -127,13 +198,14 @@ impl ConnectionHandler {
             tokio::select! {
                 msg = self.ws_stream.next() => {
                     match msg {
-                        Some(Ok(Message::Text(text))) => {
-                            if let Err(e) = self.handle_text_message(text).await {
-                                error!("Error handling message: {}", e);
-                            }
+                        // Frames that were handled fall through to the last arm
+                        Some(Ok(Message::Text(text)))
+                            if !self.receive(text.as_bytes(), WireFormat::Json).await => {
+                            break;
                         }
-                        Some(Ok(Message::Binary(_))) => {
-                            warn!("Binary messages not supported");
+                        Some(Ok(Message::Binary(data)))
+                            if !self.receive(&data, WireFormat::Binary).await => {
+                            break;
                         }
                         Some(Ok(Message::Close(_))) => {
                             info!("Connection {} closed by client", self.id);
@@ -183,8 +255,180 @@ impl ConnectionHandler {
         info!("Connection {} handler ended", self.id);
     }
 
-    async fn handle_text_message(&mut self, text: String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let message: ClientMessage = serde_json::from_str(&text)?;
+    /// Decode and handle one frame, text frames as JSON and binary ones with `wire`.
+    /// Returns false when the connection should be closed.
+    async fn receive(&mut self, data: &[u8], format: WireFormat) -> bool {
+        if let Err(reason) = self.check_limits(data.len()) {
+            return self.reject(reason).await;
+        }
+        
+        let decoded = match format {
+            WireFormat::Json => serde_json::from_slice(data).map_err(|e| e.to_string()),
+            WireFormat::Binary => wire::from_bytes(data).map_err(|e| e.to_string()),
+        };
+        let message: ClientMessage = match decoded {
+            Ok(message) => message,
+            Err(e) => {
+                debug!("Malformed message from {}: {}", self.id, e);
+                self.malformed += 1;
+                return self.reject(Rejection::MalformedMessage {
+                    strikes: self.malformed,
+                    max_strikes: self.limits.config.max_malformed,
+                }).await;
+            }
+        };
+        
+        match message {
+            ClientMessage::Hello { protocol_version, client_name, capabilities } => {
+                self.hello(protocol_version, client_name, capabilities).await
+            }
+            // Pings are answered before the handshake so clients can check the line
+            ClientMessage::Ping => self.send_message(ServerMessage::Pong).await.is_ok(),
+            _ if self.protocol_version.is_none() => {
+                self.refuse_protocol(None, "Send Hello with the protocol version before anything else".to_string()).await;
+                false
+            }
+            ClientMessage::Command { id, command } => self.handle_command(id, *command).await,
+            message => {
+                if let Err(e) = self.handle_message(message).await {
+                    error!("Error handling message: {}", e);
+                }
+                true
+            }
+        }
+    }
+
+    /// Run a command and answer it with `Ack` or `Nack`. A retried id is answered the
+    /// same way again without running the command. Returns false when the connection
+    /// should be closed.
+    async fn handle_command(&mut self, id: u64, command: ClientMessage) -> bool {
+        let seen = match self.sessions.command_outcome(self.id, id).await {
+            Some(outcome) => Some(outcome),
+            None => self.commands.outcome(id),
+        };
+        
+        let outcome = match (seen, command) {
+            (Some(outcome), _) => {
+                debug!("Connection {} retried command {}", self.id, id);
+                outcome
+            }
+            (None, ClientMessage::Hello { .. } | ClientMessage::Command { .. }) => Err(NackReason::Unsupported),
+            (None, command) => {
+                self.in_command = true;
+                if let Err(e) = self.handle_message(command).await {
+                    error!("Error handling command {}: {}", id, e);
+                    self.failure.get_or_insert(NackReason::Failed { message: e.to_string() });
+                }
+                self.in_command = false;
+                let outcome = self.failure.take().map_or(Ok(()), Err);
+                
+                // Seated players keep their commands across reconnects
+                if !self.sessions.record_command(self.id, id, outcome.clone()).await {
+                    self.commands.record(id, outcome.clone());
+                }
+                outcome
+            }
+        };
+        
+        let reply = match outcome {
+            Ok(()) => ServerMessage::Ack { id },
+            Err(reason) => ServerMessage::Nack { id, reason },
+        };
+        self.send_message(reply).await.is_ok()
+    }
+
+    /// Report why a message failed: as the `Nack` reason of a running command,
+    /// otherwise with the message sent for it before commands had ids
+    async fn fail(&mut self, reason: NackReason) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if self.in_command {
+            self.failure = Some(reason);
+            return Ok(());
+        }
+        
+        let message = match reason {
+            NackReason::NotYourTurn | NackReason::CardNotInHand | NackReason::InvalidMove { .. } => {
+                ServerMessage::InvalidMove { reason: reason.to_string() }
+            }
+            NackReason::Rejected(reason) => ServerMessage::Rejected { reason },
+            reason => ServerMessage::Error { message: reason.to_string() },
+        };
+        self.send_message(message).await
+    }
+
+    /// The game this connection plays in or watches
+    fn current_session(&self) -> Option<Uuid> {
+        self.manager.get_connection(&self.id).and_then(|conn| conn.session_id)
+    }
+
+    /// Check the client's protocol version and report the server's capabilities.
+    /// Returns false when the version is not supported.
+    async fn hello(&mut self, protocol_version: u32, client_name: String, capabilities: Vec<String>) -> bool {
+        if self.protocol_version.is_some() {
+            self.send_message(ServerMessage::Error { message: "Hello was already sent".to_string() }).await.ok();
+            return true;
+        }
+        if let Some(reason) = check_version(protocol_version) {
+            self.refuse_protocol(Some(protocol_version), reason).await;
+            return false;
+        }
+        
+        info!(
+            "Connection {} is {} on protocol {} with {:?}",
+            self.id, client_name, protocol_version, capabilities,
+        );
+        self.protocol_version = Some(protocol_version);
+        if let Some(mut conn) = self.manager.get_connection_mut(&self.id) {
+            conn.resume = capabilities.iter().any(|name| Capability::from_name(name) == Some(Capability::Resume));
+        }
+        let capabilities = self.sessions.capabilities().iter().map(|capability| capability.name().to_string()).collect();
+        self.send_message(ServerMessage::Welcome {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities,
+        }).await.is_ok()
+    }
+
+    async fn refuse_protocol(&mut self, client_version: Option<u32>, reason: String) {
+        warn!("Connection {} refused: {}", self.id, reason);
+        self.send_message(ServerMessage::UnsupportedProtocol {
+            client_version,
+            min_version: MIN_PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+            reason,
+        }).await.ok();
+        self.ws_stream.close(None).await.ok();
+    }
+
+    /// Refuse frames that are too large or arrive faster than the rate limit
+    fn check_limits(&mut self, len: usize) -> Result<(), Rejection> {
+        if len > self.limits.config.max_frame_bytes {
+            return Err(Rejection::FrameTooLarge { max_bytes: self.limits.config.max_frame_bytes });
+        }
+        self.bucket.try_take().map_err(|wait| Rejection::RateLimited {
+            retry_after_ms: wait.as_millis().min(u64::MAX as u128) as u64,
+        })
+    }
+
+    /// Tell the client why its message was refused. Returns false once the connection
+    /// has sent too many malformed messages and its address is banned.
+    async fn reject(&mut self, reason: Rejection) -> bool {
+        warn!("Connection {} rejected: {:?}", self.id, reason);
+        let banned = matches!(reason, Rejection::MalformedMessage { strikes, max_strikes } if strikes >= max_strikes);
+        self.send_message(ServerMessage::Rejected { reason }).await.ok();
+        if !banned {
+            return true;
+        }
+        
+        if let Some(ip) = self.ip {
+            self.limits.ban(ip);
+        }
+        self.send_message(ServerMessage::Rejected {
+            reason: Rejection::Banned { retry_after_ms: self.limits.config.ban_duration.as_millis() as u64 },
+        }).await.ok();
+        self.ws_stream.close(None).await.ok();
+        false
+    }
+
+    async fn handle_message(&mut self, message: ClientMessage) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         debug!("Received message from {}: {:?}", self.id, message);
         
         // Update ping time
@@ -193,14 +437,47 @@ impl ConnectionHandler {
         }
         
         match message {
+            // Answered in `receive`, and refused as a command
+            ClientMessage::Hello { .. } | ClientMessage::Command { .. } => {}
+            // Only reaches here inside a command
             ClientMessage::Ping => {
                 self.send_message(ServerMessage::Pong).await?;
             }
-            ClientMessage::JoinQueue { player_name } => {
-                self.handle_join_queue(player_name).await?;
+            ClientMessage::SetWireFormat { format } => {
+                // Confirmed in the new format, which every later message uses too
+                self.wire_format = format;
+                self.send_message(ServerMessage::WireFormatSet { format }).await?;
+            }
+            ClientMessage::JoinQueue { player_name, variant, mode, target_score, bot } => {
+                self.handle_join_queue(player_name, GameConfig { variant, mode, target_score }, bot).await?;
+            }
+            ClientMessage::CreateRoom { player_name, variant, mode, target_score } => {
+                self.handle_create_room(player_name, GameConfig { variant, mode, target_score }).await?;
             }
-            ClientMessage::Reconnect { session_token } => {
-                self.handle_reconnect(session_token).await?;
+            ClientMessage::JoinRoom { player_name, code } => {
+                self.handle_join_room(player_name, code).await?;
+            }
+            ClientMessage::Identify { identity } => {
+                self.handle_identify(identity).await?;
+            }
+            ClientMessage::Register { username, password } => {
+                let login = self.sessions.register_account(&username, &password).await;
+                self.handle_login(login).await?;
+            }
+            ClientMessage::Login { username, password } => {
+                let login = self.sessions.login(&username, &password).await;
+                self.handle_login(login).await?;
+            }
+            ClientMessage::LoginWithToken { token } => {
+                let login = self.sessions.login_with_token(&token).await;
+                self.handle_login(login).await?;
+            }
+            ClientMessage::RequestLeaderboard { limit } => {
+                let entries = self.sessions.leaderboard(limit.unwrap_or(DEFAULT_LEADERBOARD)).await;
+                self.send_message(ServerMessage::Leaderboard { entries }).await?;
+            }
+            ClientMessage::Reconnect { session_token, last_seq } => {
+                self.handle_reconnect(session_token, last_seq).await?;
             }
             ClientMessage::PlayCard { card } => {
                 self.handle_play_card(card).await?;
@@ -208,52 +485,148 @@ impl ConnectionHandler {
             ClientMessage::RequestState => {
                 self.handle_request_state().await?;
             }
+            ClientMessage::RequestRecord => {
+                self.handle_request_record().await?;
+            }
+            ClientMessage::RequestRematch => {
+                self.handle_rematch(false).await?;
+            }
+            ClientMessage::AcceptRematch => {
+                self.handle_rematch(true).await?;
+            }
+            ClientMessage::Chat { text } => {
+                self.handle_chat(text).await?;
+            }
+            ClientMessage::Emote { emote } => {
+                self.handle_emote(emote).await?;
+            }
+            ClientMessage::Mute { player, muted } => {
+                self.handle_mute(player, muted).await?;
+            }
+            ClientMessage::Spectate { session_id } => {
+                self.handle_spectate(session_id).await?;
+            }
         }
         
         Ok(())
     }
 
+    /// Send messages produced by the session manager to their connections,
+    /// numbering the ones for seated players on the way
+    async fn deliver(&self, messages: Vec<(Uuid, ServerMessage)>) {
+        for (conn_id, msg) in self.sessions.sequence(messages).await {
+            self.manager.send(&conn_id, msg).ok();
+        }
+    }
+
+    /// Answer this connection through `deliver`, so a seated player's replies are numbered
+    /// in line with everything else sent to the seat
+    async fn reply(&self, message: ServerMessage) {
+        self.deliver(vec![(self.id, message)]).await;
+    }
+
     async fn send_message(&mut self, message: ServerMessage) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let text = serde_json::to_string(&message)?;
-        self.ws_stream.send(Message::Text(text)).await?;
+        let frame = match self.wire_format {
+            WireFormat::Json => Message::Text(serde_json::to_string(&message)?),
+            WireFormat::Binary => Message::Binary(wire::to_bytes(&message)?),
+        };
+        self.ws_stream.send(frame).await?;
         Ok(())
     }
 
-    async fn handle_join_queue(&mut self, player_name: String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Update connection with player name
-        if let Some(mut conn) = self.manager.get_connection_mut(&self.id) {
-            conn.player_name = Some(player_name.clone());
+    /// Check and remember the name the player sits down under; logged-in players always use their username
+    fn set_player_name(&self, player_name: String) -> Result<String, Rejection> {
+        let player_name = validate_player_name(&player_name)?;
+        match self.manager.get_connection_mut(&self.id) {
+            Some(mut conn) => {
+                let name = conn.username.clone().unwrap_or(player_name);
+                conn.player_name = Some(name.clone());
+                Ok(name)
+            }
+            None => Ok(player_name),
         }
+    }
+
+    async fn handle_join_queue(&mut self, player_name: String, config: GameConfig, bot: Option<BotKind>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let player_name = match self.set_player_name(player_name) {
+            Ok(name) => name,
+            Err(reason) => return self.fail(NackReason::Rejected(reason)).await,
+        };
+        
+        // Try to join or create a game, or sit down with bots straight away
+        let joined = match bot {
+            Some(kind) => self.sessions.create_bot_game(self.id, player_name, config, kind).await,
+            None => self.sessions.join_or_create_game(self.id, player_name, config).await,
+        };
         
-        // Try to join or create a game
-        match self.sessions.join_or_create_game(self.id, player_name).await {
+        self.take_seat(joined, None).await
+    }
+
+    async fn handle_create_room(&mut self, player_name: String, config: GameConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let player_name = match self.set_player_name(player_name) {
+            Ok(name) => name,
+            Err(reason) => return self.fail(NackReason::Rejected(reason)).await,
+        };
+        
+        match self.sessions.create_room(self.id, player_name, config).await {
+            Ok((session_id, player_id, session_token, code)) => {
+                self.take_seat(Ok((session_id, player_id, session_token)), Some(code)).await
+            }
+            Err(e) => self.take_seat(Err(e), None).await,
+        }
+    }
+
+    async fn handle_join_room(&mut self, player_name: String, code: String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let player_name = match self.set_player_name(player_name) {
+            Ok(name) => name,
+            Err(reason) => return self.fail(NackReason::Rejected(reason)).await,
+        };
+        
+        let joined = self.sessions.join_room(self.id, player_name, &code).await;
+        self.take_seat(joined, None).await
+    }
+
+    /// Confirm the seat to the player and start the game once the table is full.
+    /// `room_code` is sent along when the player just opened a private room.
+    async fn take_seat(
+        &mut self,
+        joined: Result<(Uuid, PlayerId, String), Box<dyn std::error::Error + Send + Sync>>,
+        room_code: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match joined {
             Ok((session_id, player_id, session_token)) => {
                 // Update connection with session info
-                if let Some(mut conn) = self.manager.get_connection_mut(&self.id) {
+                let identity = self.manager.get_connection_mut(&self.id).and_then(|mut conn| {
                     conn.session_id = Some(session_id);
+                    conn.identity.clone()
+                });
+                
+                // Rate the game when the player identified themselves
+                if let Some(identity) = identity {
+                    self.sessions.set_identity(session_id, self.id, identity).await;
                 }
                 
                 // Send connection confirmation
-                self.send_message(ServerMessage::Connected {
+                self.reply(ServerMessage::Connected {
                     player_id,
                     session_token,
-                }).await?;
+                }).await;
+                
+                if let Some(code) = room_code {
+                    self.reply(ServerMessage::RoomCreated { code }).await;
+                }
                 
                 // Check if game is ready to start
                 if let Some(game_start_info) = self.sessions.check_game_ready(session_id).await {
-                    // Game is starting - send start messages to both players
-                    for (conn_id, msg) in game_start_info {
-                        if let Some(conn) = self.manager.get_connection(&conn_id) {
-                            conn.send(msg).await.ok();
-                        }
-                    }
+                    // Game is starting - send start messages to every seat
+                    self.deliver(game_start_info).await;
                 } else {
                     // Still waiting for opponent
-                    self.send_message(ServerMessage::WaitingForOpponent).await?;
+                    self.reply(ServerMessage::WaitingForOpponent).await;
                 }
             }
             Err(e) => {
-                self.send_message(ServerMessage::Error {
+                self.fail(NackReason::Failed {
                     message: format!("Failed to join game: {}", e),
                 }).await?;
             }
@@ -262,28 +635,66 @@ impl ConnectionHandler {
         Ok(())
     }
 
-    async fn handle_reconnect(&mut self, session_token: String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        match self.sessions.reconnect_player(self.id, session_token).await {
+    async fn handle_identify(&mut self, identity: Option<String>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if self.manager.get_connection(&self.id).is_some_and(|conn| conn.username.is_some()) {
+            return self.fail(NackReason::Failed {
+                message: "Already logged into an account".to_string(),
+            }).await;
+        }
+        
+        let (identity, rating) = match self.sessions.identify(identity).await {
+            Ok(identified) => identified,
+            Err(message) => return self.fail(NackReason::Failed { message }).await,
+        };
+        if let Some(mut conn) = self.manager.get_connection_mut(&self.id) {
+            conn.identity = Some(identity.clone());
+        }
+        
+        self.send_message(ServerMessage::Identified { identity, rating }).await
+    }
+
+    /// Play as the account from now on, or report why logging in failed
+    async fn handle_login(&mut self, login: Result<(Login, u32), String>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (login, rating) = match login {
+            Ok(login) => login,
+            Err(message) => return self.fail(NackReason::Failed { message }).await,
+        };
+        
+        if let Some(mut conn) = self.manager.get_connection_mut(&self.id) {
+            conn.identity = Some(login.identity.clone());
+            conn.username = Some(login.username.clone());
+        }
+        
+        self.send_message(ServerMessage::LoggedIn {
+            username: login.username,
+            identity: login.identity,
+            token: login.token,
+            rating,
+        }).await
+    }
+
+    async fn handle_reconnect(&mut self, session_token: String, last_seq: Option<u64>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let resume = self.manager.get_connection(&self.id).is_some_and(|conn| conn.resume);
+        match self.sessions.reconnect_player(self.id, session_token, last_seq, resume).await {
             Ok((session_id, player_id, messages)) => {
-                // Update connection with session info
+                // Update connection with session info and send the reconnection messages,
+                // which are numbered already
                 if let Some(mut conn) = self.manager.get_connection_mut(&self.id) {
                     conn.session_id = Some(session_id);
                 }
-                
-                // Send reconnection messages
                 for msg in messages {
-                    self.send_message(msg).await?;
+                    self.manager.send(&self.id, msg).ok();
                 }
                 
-                // Notify opponent of reconnection
-                if let Some(opponent_id) = self.sessions.get_opponent_connection(session_id, player_id).await {
-                    if let Some(conn) = self.manager.get_connection(&opponent_id) {
-                        conn.send(ServerMessage::OpponentReconnected).await.ok();
-                    }
-                }
+                // Notify the rest of the table of reconnection
+                let notifications = self.sessions.get_other_connections(session_id, player_id).await
+                    .into_iter()
+                    .map(|other_id| (other_id, ServerMessage::OpponentReconnected))
+                    .collect();
+                self.deliver(notifications).await;
             }
             Err(e) => {
-                self.send_message(ServerMessage::Error {
+                self.fail(NackReason::Failed {
                     message: format!("Failed to reconnect: {}", e),
                 }).await?;
             }
@@ -293,27 +704,17 @@ impl ConnectionHandler {
     }
 
     async fn handle_play_card(&mut self, card: crate::components::card::Card) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let conn = self.manager.get_connection(&self.id)
-            .ok_or("Connection not found")?;
-        
-        let session_id = conn.session_id
-            .ok_or("Not in a game session")?;
-        
-        drop(conn); // Release the lock
+        let Some(session_id) = self.current_session() else {
+            return self.fail(NackReason::NotInGame).await;
+        };
         
         match self.sessions.play_card(session_id, self.id, card).await {
             Ok(messages) => {
                 // Send messages to relevant players
-                for (conn_id, msg) in messages {
-                    if let Some(conn) = self.manager.get_connection(&conn_id) {
-                        conn.send(msg).await.ok();
-                    }
-                }
+                self.deliver(messages).await;
             }
             Err(e) => {
-                self.send_message(ServerMessage::InvalidMove {
-                    reason: e.to_string(),
-                }).await?;
+                self.fail(NackReason::from_play_error(e.as_ref())).await?;
             }
         }
         
@@ -321,18 +722,14 @@ impl ConnectionHandler {
     }
 
     async fn handle_request_state(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let conn = self.manager.get_connection(&self.id)
-            .ok_or("Connection not found")?;
-        
-        let session_id = conn.session_id
-            .ok_or("Not in a game session")?;
-        
-        drop(conn); // Release the lock
+        let Some(session_id) = self.current_session() else {
+            return self.fail(NackReason::NotInGame).await;
+        };
         
         if let Some(state_msg) = self.sessions.get_state_for_player(session_id, self.id).await {
-            self.send_message(state_msg).await?;
+            self.reply(state_msg).await;
         } else {
-            self.send_message(ServerMessage::Error {
+            self.fail(NackReason::Failed {
                 message: "Game session not found".to_string(),
             }).await?;
         }
@@ -340,18 +737,145 @@ impl ConnectionHandler {
         Ok(())
     }
 
+    async fn handle_request_record(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(session_id) = self.current_session() else {
+            return self.fail(NackReason::NotInGame).await;
+        };
+        
+        match self.sessions.game_record(session_id, self.id).await {
+            Ok(record) => self.reply(ServerMessage::GameRecord { record }).await,
+            Err(e) => self.fail(NackReason::Failed {
+                message: format!("No game record: {}", e),
+            }).await?,
+        }
+        
+        Ok(())
+    }
+
+    async fn handle_rematch(&mut self, accept: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(session_id) = self.current_session() else {
+            return self.fail(NackReason::NotInGame).await;
+        };
+        
+        match self.sessions.rematch(session_id, self.id, accept).await {
+            Ok(messages) => {
+                self.deliver(messages).await;
+            }
+            Err(e) => {
+                self.fail(NackReason::Failed {
+                    message: format!("No rematch: {}", e),
+                }).await?;
+            }
+        }
+        
+        Ok(())
+    }
+
+    async fn handle_chat(&mut self, text: String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(session_id) = self.current_session() else {
+            return self.fail(NackReason::NotInGame).await;
+        };
+        
+        let sent = self.sessions.chat(session_id, self.id, &text).await;
+        self.relay_chat(sent).await
+    }
+
+    async fn handle_emote(&mut self, emote: Emote) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(session_id) = self.current_session() else {
+            return self.fail(NackReason::NotInGame).await;
+        };
+        
+        let sent = self.sessions.emote(session_id, self.id, emote).await;
+        self.relay_chat(sent).await
+    }
+
+    /// Deliver a chat line or emote to the table, or tell the sender why it was refused
+    async fn relay_chat(
+        &mut self,
+        sent: Result<Vec<(Uuid, ServerMessage)>, Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match sent {
+            Ok(messages) => {
+                self.deliver(messages).await;
+            }
+            Err(e) => {
+                self.fail(NackReason::Failed {
+                    message: format!("Message not sent: {}", e),
+                }).await?;
+            }
+        }
+        
+        Ok(())
+    }
+
+    async fn handle_mute(&mut self, player: PlayerId, muted: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(session_id) = self.current_session() else {
+            return self.fail(NackReason::NotInGame).await;
+        };
+        
+        if let Err(e) = self.sessions.mute(session_id, self.id, player, muted).await {
+            self.fail(NackReason::Failed {
+                message: format!("Cannot mute: {}", e),
+            }).await?;
+        }
+        
+        Ok(())
+    }
+
+    async fn handle_spectate(&mut self, session_id: Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match self.sessions.spectate(self.id, session_id).await {
+            Ok((state, notifications)) => {
+                if let Some(mut conn) = self.manager.get_connection_mut(&self.id) {
+                    conn.session_id = Some(session_id);
+                }
+                
+                self.send_message(state).await?;
+                
+                // Tell the players how many are watching now
+                self.deliver(notifications).await;
+            }
+            Err(e) => {
+                self.fail(NackReason::Failed {
+                    message: format!("Failed to spectate: {}", e),
+                }).await?;
+            }
+        }
+        
+        Ok(())
+    }
+
     async fn handle_disconnect(&mut self) {
         // Remove from connection manager
         if let Some(conn) = self.manager.remove_connection(&self.id) {
             if let Some(session_id) = conn.session_id {
                 // Notify session manager of disconnection
-                if let Some((opponent_id, timeout_msg)) = self.sessions.handle_player_disconnect(session_id, self.id).await {
-                    // Notify opponent
-                    if let Some(opp_conn) = self.manager.get_connection(&opponent_id) {
-                        opp_conn.send(timeout_msg).await.ok();
-                    }
-                }
+                self.deliver(self.sessions.handle_player_disconnect(session_id, self.id).await).await;
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_manager_send_queues_without_a_held_entry() {
+        let manager = ConnectionManager::new();
+        let id = Uuid::new_v4();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        manager.add_connection(id, Connection::new(id, tx));
+        
+        // Clients without `resume` get the bare message
+        let sequenced = ServerMessage::Sequenced { seq: 1, message: Box::new(ServerMessage::Pong) };
+        manager.send(&id, sequenced.clone()).unwrap();
+        assert!(matches!(rx.try_recv(), Ok(ServerMessage::Pong)));
+        
+        manager.get_connection_mut(&id).unwrap().resume = true;
+        manager.send(&id, sequenced).unwrap();
+        assert!(matches!(rx.try_recv(), Ok(ServerMessage::Sequenced { seq: 1, .. })));
+        
+        // The entry is free again, so it can be removed straight away
+        manager.remove_connection(&id).unwrap();
+        assert!(manager.send(&id, ServerMessage::Pong).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use std::time::{Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::components::card::{Card, PlayerId};
+use crate::server::game_engine::KseriGameState;
+use crate::server::messages::{GameEvent, GameEventType};
+
+/// Complete, append-only history of a session. Nothing is ever dropped, so the
+/// log can rebuild the game and settle disputes about what was played.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameLog {
+    events: Vec<GameEvent>,
+}
+
+impl GameLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an event, stamping it with the next sequence number and the current time
+    pub fn append(&mut self, session_id: Uuid, event_type: GameEventType) -> &GameEvent {
+        self.events.push(GameEvent {
+            session_id,
+            sequence: self.events.len() as u64,
+            event_type,
+            timestamp: Instant::now(),
+            recorded_at: SystemTime::now(),
+        });
+        &self.events[self.events.len() - 1]
+    }
+
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Events from `sequence` on
+    pub fn since(&self, sequence: u64) -> &[GameEvent] {
+        &self.events[(sequence as usize).min(self.events.len())..]
+    }
+
+    /// Every card `player` played, in order
+    pub fn plays_by(&self, player: PlayerId) -> impl Iterator<Item = &GameEvent> {
+        self.events.iter()
+            .filter(move |event| matches!(event.event_type, GameEventType::CardPlayed { player: p, .. } if p == player))
+    }
+
+    /// The event where `player` played `card`, if they ever did
+    pub fn find_play(&self, player: PlayerId, card: Card) -> Option<&GameEvent> {
+        self.plays_by(player)
+            .find(|event| matches!(event.event_type, GameEventType::CardPlayed { card: c, .. } if c == card))
+    }
+
+    /// Rebuild the current deal from its `DealStarted` event and the plays after it,
+    /// checking that every play captures what the log says it did
+    pub fn rebuild(&self) -> Result<KseriGameState, String> {
+        let mut state: Option<KseriGameState> = None;
+
+        for event in &self.events {
+            match &event.event_type {
+                GameEventType::DealStarted { seed, config, opening_lead } => {
+                    state = Some(KseriGameState::with_opening_lead(*seed, *config, *opening_lead));
+                }
+                GameEventType::CardPlayed { player, card, captured, is_kseri } => {
+                    let game = state.as_mut()
+                        .ok_or_else(|| format!("Event {}: card played before any deal", event.sequence))?;
+                    let result = game.play_card(*player, *card)
+                        .map_err(|e| format!("Event {}: {}", event.sequence, e))?;
+                    if result.captured_cards != *captured || result.is_kseri != *is_kseri {
+                        return Err(format!("Event {}: logged capture does not match the play", event.sequence));
+                    }
+                }
+                GameEventType::Forfeited { player } => {
+                    state.as_mut()
+                        .ok_or_else(|| format!("Event {}: forfeit before any deal", event.sequence))?
+                        .forfeit(*player);
+                }
+                _ => {}
+            }
+        }
+
+        state.ok_or_else(|| "No deal in the log".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::rules::GameConfig;
+
+    /// Play a deal to the end, logging it the way the session manager does
+    fn logged_game(seed: u64) -> (GameLog, KseriGameState) {
+        let session_id = Uuid::new_v4();
+        let mut log = GameLog::new();
+        let mut state = KseriGameState::with_seed(seed);
+        log.append(session_id, GameEventType::DealStarted {
+            seed,
+            config: GameConfig::default(),
+            opening_lead: state.opening_lead,
+        });
+
+        while !state.game_over {
+            let player = state.current_turn;
+            let card = state.player_hands[player.index()][0];
+            let result = state.play_card(player, card).unwrap();
+            log.append(session_id, GameEventType::CardPlayed {
+                player,
+                card,
+                captured: result.captured_cards,
+                is_kseri: result.is_kseri,
+            });
+        }
+
+        (log, state)
+    }
+
+    #[test]
+    fn test_log_keeps_everything_in_order() {
+        let (log, state) = logged_game(5);
+
+        // No ring buffer: the deal event and all 48 plays are still there
+        assert_eq!(log.len(), 49);
+        assert!(log.events().iter().enumerate().all(|(idx, event)| event.sequence == idx as u64));
+        assert_eq!(log.since(47).len(), 2);
+        assert!(log.since(100).is_empty());
+
+        let plays_by_one = log.plays_by(PlayerId::PLAYER_ONE).count();
+        assert_eq!(plays_by_one, 24);
+
+        // Settle "I never played that card"
+        let (player, card) = state.move_history[10];
+        assert_eq!(log.find_play(player, card).unwrap().sequence, 11);
+        assert!(log.find_play(PlayerId(1 - player.0), card).is_none());
+    }
+
+    #[test]
+    fn test_rebuild_from_log() {
+        let (log, state) = logged_game(9);
+
+        let rebuilt = log.rebuild().unwrap();
+        assert_eq!(rebuilt.player_scores, state.player_scores);
+        assert_eq!(rebuilt.move_history, state.move_history);
+        assert!(rebuilt.game_over);
+
+        // A doctored capture is caught
+        let mut doctored = log.clone();
+        if let GameEventType::CardPlayed { captured, .. } = &mut doctored.events[1].event_type {
+            captured.push(Card::new(crate::components::card::Suit::Hearts, crate::components::card::Rank::Ace));
+        }
+        assert!(doctored.rebuild().unwrap_err().starts_with("Event 1"));
+
+        assert!(GameLog::new().rebuild().is_err());
+    }
+}
@@ -1,35 +1,94 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::components::card::{Card, Rank, PlayerId};
 #[cfg(test)]
 use crate::components::card::Suit;
 use crate::components::table::Deck;
+use crate::server::rules::{GameConfig, GameMode, RuleSet, RuleVariant};
+
+/// Cards dealt to each seat per round; 4 + 4 × seats × rounds uses the whole deck for 2 to 4 seats
+const HAND_SIZE: usize = 4;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KseriGameState {
     pub deck: Vec<Card>,
-    pub player_hands: [Vec<Card>; 2],
+    /// One hand per seat
+    pub player_hands: Vec<Vec<Card>>,
+    /// Table pile, bottom first
     pub table_cards: Vec<Card>,
-    pub player_scores: [Vec<Card>; 2],
+    /// How many cards at the bottom of `table_cards` are still face down
+    #[serde(default)]
+    pub table_hidden: usize,
+    /// Seat that captured the face-down opening cards, and how many there were.
+    /// They sit at the bottom of that seat's score pile.
+    #[serde(default)]
+    pub hidden_capture: Option<(PlayerId, usize)>,
+    /// Captured cards per seat
+    pub player_scores: Vec<Vec<Card>>,
     pub current_turn: PlayerId,
     pub last_capture_player: Option<PlayerId>,
     pub game_over: bool,
-    pub kseri_count: [u32; 2],
+    pub kseri_count: Vec<u32>,
+    /// Jack-on-Jack kseris, scored separately when the variant allows them
+    pub jack_kseri_count: Vec<u32>,
+    pub config: GameConfig,
+    /// Seed the deck was shuffled with; together with `move_history` it reproduces the game
+    pub seed: u64,
+    /// Seat that made the first play of this deal
+    pub opening_lead: PlayerId,
+    /// Every accepted play, in order
+    pub move_history: Vec<(PlayerId, Card)>,
+    /// Seat that gave up, ending the deal before the cards ran out
+    #[serde(default)]
+    pub forfeited: Option<PlayerId>,
 }
 
 impl KseriGameState {
     pub fn new() -> Self {
+        Self::with_seed(rand::random())
+    }
+    
+    /// Create a game whose deal is fully determined by `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_variant(seed, RuleVariant::default())
+    }
+    
+    /// Create a seeded heads-up game played under the given rule variant
+    pub fn with_variant(seed: u64, variant: RuleVariant) -> Self {
+        Self::with_config(seed, GameConfig { variant, ..Default::default() })
+    }
+    
+    /// Create a seeded game with the given rules and seating
+    pub fn with_config(seed: u64, config: GameConfig) -> Self {
+        Self::with_opening_lead(seed, config, PlayerId::PLAYER_ONE)
+    }
+    
+    /// Create a seeded game where `opening_lead` plays first
+    pub fn with_opening_lead(seed: u64, config: GameConfig, opening_lead: PlayerId) -> Self {
+        let seats = config.mode.seat_count();
+        // A fixed algorithm rather than `StdRng`, so a seed deals the same on every platform and release
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
         let mut deck = Deck::new();
-        deck.shuffle();
+        deck.shuffle_with(&mut rng);
         let mut state = Self {
             deck: deck.cards,
-            player_hands: [Vec::new(), Vec::new()],
+            player_hands: vec![Vec::new(); seats],
             table_cards: Vec::new(),
-            player_scores: [Vec::new(), Vec::new()],
-            current_turn: PlayerId::PLAYER_ONE,
+            table_hidden: 0,
+            hidden_capture: None,
+            player_scores: vec![Vec::new(); seats],
+            current_turn: opening_lead,
             last_capture_player: None,
             game_over: false,
-            kseri_count: [0, 0],
+            kseri_count: vec![0; seats],
+            jack_kseri_count: vec![0; seats],
+            config,
+            seed,
+            opening_lead,
+            move_history: Vec::new(),
+            forfeited: None,
         };
         
         // Deal initial cards
@@ -38,6 +97,30 @@ impl KseriGameState {
         state
     }
     
+    /// Rebuild a game from its seed and the ordered list of plays, validating every move
+    pub fn replay(seed: u64, config: GameConfig, opening_lead: PlayerId, moves: &[(PlayerId, Card)]) -> Result<Self, String> {
+        let mut state = Self::with_opening_lead(seed, config, opening_lead);
+        
+        for (idx, (player, card)) in moves.iter().enumerate() {
+            state.play_card(*player, *card)
+                .map_err(|e| format!("Move {} ({:?} plays {}): {}", idx + 1, player, card, e))?;
+        }
+        
+        Ok(state)
+    }
+    
+    pub fn rules(&self) -> RuleSet {
+        self.config.variant.rules()
+    }
+    
+    pub fn mode(&self) -> GameMode {
+        self.config.mode
+    }
+    
+    pub fn seat_count(&self) -> usize {
+        self.player_hands.len()
+    }
+    
     fn deal_initial_cards(&mut self) {
         // Deal 4 cards to table
         for _ in 0..4 {
@@ -46,14 +129,23 @@ impl KseriGameState {
             }
         }
         
-        // Deal 4 cards to each player
-        for _ in 0..4 {
-            for player_idx in 0..2 {
-                if let Some(card) = self.deck.pop() {
-                    self.player_hands[player_idx].push(card);
-                }
+        // A Jack may not be the face-up card: bury it under the stock and turn up another
+        while self.table_cards.last().is_some_and(|top| top.rank == Rank::Jack) && !self.deck.is_empty() {
+            if let (Some(jack), Some(replacement)) = (self.table_cards.pop(), self.deck.pop()) {
+                self.deck.insert(0, jack);
+                self.table_cards.push(replacement);
             }
         }
+        
+        // Only the top card is shown
+        self.table_hidden = self.table_cards.len().saturating_sub(1);
+        
+        self.deal_new_hands();
+    }
+    
+    /// Table cards everyone can see, bottom first
+    pub fn visible_table_cards(&self) -> &[Card] {
+        &self.table_cards[self.table_hidden.min(self.table_cards.len())..]
     }
     
     pub fn deal_new_hands(&mut self) -> bool {
@@ -61,9 +153,9 @@ impl KseriGameState {
             return false;
         }
         
-        // Deal 4 cards to each player
-        for _ in 0..4 {
-            for player_idx in 0..2 {
+        // Deal a hand to each seat, one card at a time around the table
+        for _ in 0..HAND_SIZE {
+            for player_idx in 0..self.seat_count() {
                 if let Some(card) = self.deck.pop() {
                     self.player_hands[player_idx].push(card);
                 }
@@ -73,25 +165,31 @@ impl KseriGameState {
         true
     }
     
-    pub fn play_card(&mut self, player: PlayerId, card: Card) -> Result<PlayCardResult, String> {
+    pub fn play_card(&mut self, player: PlayerId, card: Card) -> Result<PlayCardResult, PlayError> {
+        if self.game_over {
+            return Err(PlayError::GameOver);
+        }
+        
         // Validate it's the player's turn
         if self.current_turn != player {
-            return Err("Not your turn".to_string());
+            return Err(PlayError::NotYourTurn);
         }
         
         // Validate player has the card
-        let player_idx = player.0 as usize;
+        let player_idx = player.index();
         let card_pos = self.player_hands[player_idx]
             .iter()
             .position(|c| c == &card)
-            .ok_or("Card not in hand")?;
+            .ok_or(PlayError::CardNotInHand)?;
         
         // Remove card from hand
         self.player_hands[player_idx].remove(card_pos);
+        self.move_history.push((player, card));
         
         // Check for capture
         let mut captured_cards = Vec::new();
         let mut is_kseri = false;
+        let mut hidden_captured = 0;
         
         if !self.table_cards.is_empty() {
             let should_capture = if card.rank == Rank::Jack {
@@ -108,10 +206,21 @@ impl KseriGameState {
                 // Capture all table cards
                 captured_cards = std::mem::take(&mut self.table_cards);
                 
+                // Face-down cards go to the capturer, who is the only one to see them
+                if self.table_hidden > 0 {
+                    hidden_captured = std::mem::take(&mut self.table_hidden);
+                    self.hidden_capture = Some((player, hidden_captured));
+                }
+                
                 // Check for Kseri
-                if captured_cards.len() == 1 && card.rank != Rank::Jack && captured_cards[0].rank == card.rank {
-                    is_kseri = true;
-                    self.kseri_count[player_idx] += 1;
+                if captured_cards.len() == 1 && captured_cards[0].rank == card.rank {
+                    if card.rank != Rank::Jack {
+                        is_kseri = true;
+                        self.kseri_count[player_idx] += 1;
+                    } else if self.rules().jack_kseri_bonus.is_some() {
+                        is_kseri = true;
+                        self.jack_kseri_count[player_idx] += 1;
+                    }
                 }
                 
                 // Add played card to captured cards
@@ -130,12 +239,14 @@ impl KseriGameState {
             self.table_cards.push(card);
         }
         
-        // Switch turns
-        self.current_turn = if player == PlayerId::PLAYER_ONE { PlayerId::PLAYER_TWO } else { PlayerId::PLAYER_ONE };
+        // Pass the turn to the next seat around the table
+        self.current_turn = player.next(self.seat_count());
         
         // Check if hands are empty
-        if self.player_hands[0].is_empty() && self.player_hands[1].is_empty() {
-            if !self.deal_new_hands() {
+        let mut cards_dealt = false;
+        if self.player_hands.iter().all(|hand| hand.is_empty()) {
+            cards_dealt = self.deal_new_hands();
+            if !cards_dealt {
                 // No more cards to deal - game ending
                 self.handle_game_end();
             }
@@ -143,7 +254,9 @@ impl KseriGameState {
         
         Ok(PlayCardResult {
             captured_cards,
+            hidden_captured,
             is_kseri,
+            cards_dealt,
             game_ended: self.game_over,
         })
     }
@@ -155,74 +268,130 @@ impl KseriGameState {
         if !self.table_cards.is_empty() {
             if let Some(last_player) = self.last_capture_player {
                 let remaining = std::mem::take(&mut self.table_cards);
-                self.player_scores[last_player.0 as usize].extend(remaining);
+                self.player_scores[last_player.index()].extend(remaining);
+                self.table_hidden = 0;
             }
         }
     }
     
-    pub fn calculate_scores(&self) -> [u32; 2] {
-        let mut scores = [0u32; 2];
+    /// End the deal where it stands because `seat` gave up; nothing more can be played
+    pub fn forfeit(&mut self, seat: PlayerId) {
+        self.game_over = true;
+        self.forfeited = Some(seat);
+    }
+    
+    /// Points each seat earned on its own: captured card values plus kseri bonuses
+    pub fn seat_points(&self) -> Vec<u32> {
+        let rules = self.rules();
         
-        for (player_idx, captured) in self.player_scores.iter().enumerate() {
-            for card in captured {
-                scores[player_idx] += card.kseri_value();
-            }
-            
-            // Add Kseri bonuses (10 points each)
-            scores[player_idx] += self.kseri_count[player_idx] * 10;
-        }
+        self.player_scores.iter().enumerate()
+            .map(|(player_idx, captured)| {
+                let card_points = captured.iter()
+                    .fold(0u32, |total, card| total.saturating_add(rules.card_value(card)));
+                card_points
+                    .saturating_add(self.kseri_count[player_idx].saturating_mul(rules.kseri_bonus))
+                    .saturating_add(self.jack_kseri_count[player_idx].saturating_mul(rules.jack_kseri_bonus.unwrap_or(0)))
+            })
+            .collect()
+    }
+    
+    /// Final scores per team; outside partnership play each seat is its own team
+    pub fn calculate_scores(&self) -> Vec<u32> {
+        let mode = self.mode();
+        let mut scores = vec![0u32; mode.team_count()];
+        let mut card_counts = vec![0usize; mode.team_count()];
         
-        // Award 3 points for majority of cards
-        let card_counts = [
-            self.player_scores[0].len(),
-            self.player_scores[1].len(),
-        ];
+        for (player_idx, points) in self.seat_points().into_iter().enumerate() {
+            let team = mode.team_of(player_idx);
+            scores[team] = scores[team].saturating_add(points);
+            card_counts[team] += self.player_scores[player_idx].len();
+        }
         
-        if card_counts[0] > card_counts[1] {
-            scores[0] += 3;
-        } else if card_counts[1] > card_counts[0] {
-            scores[1] += 3;
+        // Award the bonus for majority of cards, unless the lead is shared
+        if let Some(team) = unique_max(&card_counts) {
+            scores[team] = scores[team].saturating_add(self.rules().majority_bonus);
         }
         
         scores
     }
     
-    pub fn get_winner(&self) -> Option<PlayerId> {
+    /// Team with the highest score once the game is over, `None` for a tie
+    pub fn get_winning_team(&self) -> Option<usize> {
         if !self.game_over {
             return None;
         }
         
-        let scores = self.calculate_scores();
-        if scores[0] > scores[1] {
-            Some(PlayerId::PLAYER_ONE)
-        } else if scores[1] > scores[0] {
-            Some(PlayerId::PLAYER_TWO)
-        } else {
-            None // Tie
+        unique_max(&self.calculate_scores())
+    }
+    
+    /// Winning seat once the game is over. Partnership games are won by a team,
+    /// so this is always `None` there; use `get_winning_team` instead.
+    pub fn get_winner(&self) -> Option<PlayerId> {
+        if self.mode().is_partnership() {
+            return None;
+        }
+        
+        self.get_winning_team().map(|team| PlayerId(team as u8))
+    }
+    
+    /// Everything the player in `seat` is allowed to know about the game
+    pub fn player_view(&self, seat: PlayerId) -> PlayerView {
+        let mut captured = self.player_scores.clone();
+        let mut captured_hidden = vec![0; self.seat_count()];
+        
+        // Face-down cards at the bottom of another seat's pile were never shown to this seat
+        if let Some((capturer, count)) = self.hidden_capture {
+            if capturer != seat {
+                captured[capturer.index()].drain(..count);
+                captured_hidden[capturer.index()] = count;
+            }
+        }
+        
+        PlayerView {
+            seat,
+            config: self.config,
+            hand: self.player_hands[seat.index()].clone(),
+            table_cards: self.visible_table_cards().to_vec(),
+            table_hidden: self.table_hidden,
+            hand_counts: self.player_hands.iter().map(Vec::len).collect(),
+            deck_remaining: self.deck.len(),
+            captured,
+            captured_hidden,
+            kseri_count: self.kseri_count.clone(),
+            jack_kseri_count: self.jack_kseri_count.clone(),
+            current_turn: self.current_turn,
+            last_capture_player: self.last_capture_player,
         }
     }
     
     pub fn validate_state(&self) -> Result<(), String> {
         // Validate total cards
         let total_cards = self.deck.len() 
-            + self.player_hands[0].len() 
-            + self.player_hands[1].len()
+            + self.player_hands.iter().map(Vec::len).sum::<usize>()
             + self.table_cards.len()
-            + self.player_scores[0].len()
-            + self.player_scores[1].len();
+            + self.player_scores.iter().map(Vec::len).sum::<usize>();
             
         if total_cards != 52 {
             return Err(format!("Invalid card count: {}", total_cards));
         }
         
+        // Face-down cards must still be on the table, or at the bottom of their capturer's pile
+        if self.table_hidden > self.table_cards.len() {
+            return Err(format!("{} face-down cards but only {} on the table", self.table_hidden, self.table_cards.len()));
+        }
+        if let Some((player, count)) = self.hidden_capture {
+            let pile_len = self.player_scores.get(player.index()).map(Vec::len).unwrap_or(0);
+            if self.table_hidden > 0 || count > pile_len {
+                return Err(format!("Inconsistent face-down capture of {} cards by {:?}", count, player));
+            }
+        }
+        
         // Check for duplicate cards
         let mut all_cards: Vec<Card> = Vec::new();
         all_cards.extend(self.deck.clone());
-        all_cards.extend(self.player_hands[0].clone());
-        all_cards.extend(self.player_hands[1].clone());
+        all_cards.extend(self.player_hands.iter().flatten());
         all_cards.extend(self.table_cards.clone());
-        all_cards.extend(self.player_scores[0].clone());
-        all_cards.extend(self.player_scores[1].clone());
+        all_cards.extend(self.player_scores.iter().flatten());
         
         all_cards.sort();
         let original_len = all_cards.len();
@@ -236,12 +405,87 @@ impl KseriGameState {
     }
 }
 
+/// One seat's view of the game: its own hand plus everything shown face up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub seat: PlayerId,
+    pub config: GameConfig,
+    pub hand: Vec<Card>,
+    /// Face-up table cards, bottom first
+    pub table_cards: Vec<Card>,
+    pub table_hidden: usize,
+    pub hand_counts: Vec<usize>,
+    pub deck_remaining: usize,
+    /// Captured cards this seat has seen, indexed by seat
+    pub captured: Vec<Vec<Card>>,
+    /// Captured cards this seat never saw (face-down captures by others), indexed by seat
+    pub captured_hidden: Vec<usize>,
+    pub kseri_count: Vec<u32>,
+    pub jack_kseri_count: Vec<u32>,
+    pub current_turn: PlayerId,
+    pub last_capture_player: Option<PlayerId>,
+}
+
+impl PlayerView {
+    /// Cards whose location this seat knows: its hand, the face-up table and seen captures
+    pub fn seen_cards(&self) -> Vec<Card> {
+        self.hand.iter()
+            .chain(&self.table_cards)
+            .chain(self.captured.iter().flatten())
+            .copied()
+            .collect()
+    }
+    
+    /// Cards this seat has not seen yet: other hands, the deck, and face-down cards
+    pub fn unseen_cards(&self) -> Vec<Card> {
+        let seen = self.seen_cards();
+        Deck::new().cards.into_iter().filter(|card| !seen.contains(card)).collect()
+    }
+}
+
+/// Why a play was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayError {
+    NotYourTurn,
+    CardNotInHand,
+    GameOver,
+}
+
+impl std::fmt::Display for PlayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayError::NotYourTurn => f.write_str("Not your turn"),
+            PlayError::CardNotInHand => f.write_str("Card not in hand"),
+            PlayError::GameOver => f.write_str("Game is over"),
+        }
+    }
+}
+
+impl std::error::Error for PlayError {}
+
 pub struct PlayCardResult {
     pub captured_cards: Vec<Card>,
+    /// Leading entries of `captured_cards` that were face down; only the capturer may see them
+    pub hidden_captured: usize,
     pub is_kseri: bool,
+    /// Every hand ran out and a fresh round was dealt from the deck
+    pub cards_dealt: bool,
     pub game_ended: bool,
 }
 
+/// Index of the strictly largest value, `None` when the maximum is shared
+pub fn unique_max<T: Ord + Copy>(values: &[T]) -> Option<usize> {
+    let max = *values.iter().max()?;
+    let mut leaders = values.iter().enumerate().filter(|(_, v)| **v == max);
+    let (idx, _) = leaders.next()?;
+    
+    if leaders.next().is_some() {
+        None
+    } else {
+        Some(idx)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,6 +497,9 @@ mod tests {
         // Check initial deal
         assert_eq!(state.deck.len(), 40); // 52 - 4 (table) - 4 (P1) - 4 (P2)
         assert_eq!(state.table_cards.len(), 4);
+        assert_eq!(state.table_hidden, 3);
+        assert_eq!(state.visible_table_cards().len(), 1);
+        assert_ne!(state.table_cards[3].rank, Rank::Jack);
         assert_eq!(state.player_hands[0].len(), 4);
         assert_eq!(state.player_hands[1].len(), 4);
         assert_eq!(state.player_scores[0].len(), 0);
@@ -270,6 +517,7 @@ mod tests {
         
         // Set up a known table state
         state.table_cards = vec![Card { suit: Suit::Hearts, rank: Rank::Seven }];
+        state.table_hidden = 0;
         state.player_hands[0] = vec![
             Card { suit: Suit::Diamonds, rank: Rank::Seven },
             Card { suit: Suit::Clubs, rank: Rank::Ace },
@@ -295,6 +543,7 @@ mod tests {
             Card { suit: Suit::Clubs, rank: Rank::King },
             Card { suit: Suit::Diamonds, rank: Rank::Three },
         ];
+        state.table_hidden = 0;
         state.player_hands[0] = vec![
             Card { suit: Suit::Spades, rank: Rank::Jack },
         ];
@@ -307,4 +556,246 @@ mod tests {
         assert_eq!(state.table_cards.len(), 0);
         assert_eq!(state.player_scores[0].len(), 4);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_same_seed_same_deal() {
+        let a = KseriGameState::with_seed(42);
+        let b = KseriGameState::with_seed(42);
+        let c = KseriGameState::with_seed(43);
+        
+        assert_eq!(a.seed, 42);
+        assert_eq!(a.deck, b.deck);
+        assert_eq!(a.table_cards, b.table_cards);
+        assert_eq!(a.player_hands, b.player_hands);
+        assert_ne!(a.deck, c.deck);
+    }
+    
+    #[test]
+    fn test_seed_deals_a_fixed_hand() {
+        // Pinned so a change of random number generator or shuffle cannot go unnoticed:
+        // saved games and records are replayed from their seed
+        let state = KseriGameState::with_seed(42);
+        let cards = |cards: &[Card]| cards.iter().map(|card| card.to_string()).collect::<Vec<_>>().join(" ");
+        assert_eq!(cards(&state.table_cards), "Q♥ 9♣ 8♥ 8♠");
+        assert_eq!(cards(&state.player_hands[0]), "Q♣ A♦ 7♠ A♣");
+        assert_eq!(cards(&state.player_hands[1]), "8♦ 2♦ J♠ 6♣");
+        assert_eq!(cards(&state.deck[..4]), "4♦ 5♣ 9♦ A♥");
+    }
+    
+    #[test]
+    fn test_replay_reproduces_game() {
+        let mut state = KseriGameState::with_seed(7);
+        
+        // Play the whole game, always leading the first card in hand
+        while !state.game_over {
+            let player = state.current_turn;
+            let card = state.player_hands[player.0 as usize][0];
+            state.play_card(player, card).unwrap();
+        }
+        
+        let replayed = KseriGameState::replay(state.seed, state.config, state.opening_lead, &state.move_history).unwrap();
+        assert!(replayed.game_over);
+        assert_eq!(replayed.player_scores, state.player_scores);
+        assert_eq!(replayed.calculate_scores(), state.calculate_scores());
+        
+        // A move that was never legal is rejected
+        let mut bad_moves = state.move_history.clone();
+        bad_moves.swap(0, 1);
+        assert!(KseriGameState::replay(state.seed, state.config, state.opening_lead, &bad_moves).is_err());
+    }
+    
+    #[test]
+    fn test_jack_on_jack_pisti() {
+        let jack_on_table = Card { suit: Suit::Hearts, rank: Rank::Jack };
+        let jack_in_hand = Card { suit: Suit::Spades, rank: Rank::Jack };
+        
+        for (variant, expect_kseri) in [(RuleVariant::Classic, false), (RuleVariant::Pisti, true)] {
+            let mut state = KseriGameState::with_variant(1, variant);
+            state.table_cards = vec![jack_on_table];
+            state.table_hidden = 0;
+            state.player_hands[0] = vec![jack_in_hand];
+            state.player_hands[1] = vec![Card { suit: Suit::Clubs, rank: Rank::Four }];
+            
+            let result = state.play_card(PlayerId::PLAYER_ONE, jack_in_hand).unwrap();
+            assert_eq!(result.is_kseri, expect_kseri);
+            
+            let scores = state.calculate_scores();
+            if expect_kseri {
+                // Two Jacks (1 each), a 20-point pişti and the majority bonus
+                assert_eq!(scores[0], 1 + 1 + 20 + 3);
+            } else {
+                // Jack of Hearts scores nothing in Classic; majority bonus only
+                assert_eq!(scores[0], 3);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_partnership_game_plays_out() {
+        let config = GameConfig { mode: GameMode::Partnership, ..Default::default() };
+        let mut state = KseriGameState::with_config(3, config);
+        
+        // 4 table cards plus four hands of 4 leaves 32 cards, two more full rounds
+        assert_eq!(state.player_hands.len(), 4);
+        assert!(state.player_hands.iter().all(|hand| hand.len() == 4));
+        assert_eq!(state.deck.len(), 32);
+        
+        let mut turn_order = Vec::new();
+        let mut deals = 0;
+        while !state.game_over {
+            let player = state.current_turn;
+            turn_order.push(player.0);
+            let card = state.player_hands[player.0 as usize][0];
+            let result = state.play_card(player, card).unwrap();
+            if result.cards_dealt {
+                deals += 1;
+            }
+            assert!(state.validate_state().is_ok());
+        }
+        
+        assert_eq!(deals, 2);
+        assert_eq!(turn_order.len(), 48);
+        assert!(turn_order.chunks(4).all(|round| round == [0, 1, 2, 3]));
+        
+        // Team totals are the partners' contributions plus the majority bonus
+        let seat_points = state.seat_points();
+        let team_scores = state.calculate_scores();
+        let team_zero_cards = state.player_scores[0].len() + state.player_scores[2].len();
+        let majority_bonus = if team_zero_cards > 26 { state.rules().majority_bonus } else { 0 };
+        assert_eq!(team_scores.len(), 2);
+        assert_eq!(team_scores[0], seat_points[0] + seat_points[2] + majority_bonus);
+        assert_eq!(state.get_winner(), None);
+    }
+    
+    #[test]
+    fn test_every_mode_deals_the_whole_deck() {
+        for mode in GameMode::all() {
+            let seats = mode.seat_count();
+            let mut state = KseriGameState::with_config(11, GameConfig { mode, ..Default::default() });
+            assert_eq!(state.deck.len(), 52 - 4 - seats * HAND_SIZE);
+            
+            let mut plays = 0;
+            while !state.game_over {
+                let player = state.current_turn;
+                let card = state.player_hands[player.index()][0];
+                state.play_card(player, card).unwrap();
+                plays += 1;
+            }
+            
+            // Every card except the opening table ends up played, and nothing is left over
+            assert_eq!(plays, 48);
+            assert!(state.deck.is_empty());
+            assert!(state.validate_state().is_ok());
+            assert_eq!(state.calculate_scores().len(), mode.team_count());
+        }
+    }
+    
+    #[test]
+    fn test_three_player_majority_and_winner() {
+        let config = GameConfig { mode: GameMode::ThreePlayer, ..Default::default() };
+        let mut state = KseriGameState::with_config(5, config);
+        state.game_over = true;
+        state.player_scores = vec![
+            vec![Card { suit: Suit::Clubs, rank: Rank::Two }, Card { suit: Suit::Hearts, rank: Rank::Five }],
+            vec![Card { suit: Suit::Diamonds, rank: Rank::Ten }],
+            vec![Card { suit: Suit::Spades, rank: Rank::Ace }],
+        ];
+        
+        // Seat 0: 2 points + 3 majority, seat 1: 3 points, seat 2: 1 point
+        assert_eq!(state.calculate_scores(), vec![5, 3, 1]);
+        assert_eq!(state.get_winner(), Some(PlayerId::PLAYER_ONE));
+        
+        // A shared card lead earns nobody the majority bonus
+        state.player_scores[1].push(Card { suit: Suit::Hearts, rank: Rank::Six });
+        assert_eq!(state.calculate_scores(), vec![2, 3, 1]);
+        assert_eq!(state.get_winner(), Some(PlayerId::PLAYER_TWO));
+    }
+    
+    /// Deal a heads-up game from explicit cards; everything else stays in the deck
+    fn stacked(table: &[Card], hidden: usize, hands: [&[Card]; 2]) -> KseriGameState {
+        let mut state = KseriGameState::with_seed(0);
+        let placed: Vec<Card> = table.iter().chain(hands[0]).chain(hands[1]).copied().collect();
+        
+        state.deck = Deck::new().cards.into_iter().filter(|c| !placed.contains(c)).collect();
+        state.table_cards = table.to_vec();
+        state.table_hidden = hidden;
+        state.player_hands = vec![hands[0].to_vec(), hands[1].to_vec()];
+        state
+    }
+    
+    #[test]
+    fn test_jack_on_top_is_buried() {
+        let mut state = KseriGameState::with_seed(9);
+        
+        // Stack the deck so a Jack would be turned up (cards are drawn from the end)
+        let jack = Card { suit: Suit::Clubs, rank: Rank::Jack };
+        let mut deck: Vec<Card> = Deck::new().cards.into_iter().filter(|c| *c != jack).collect();
+        deck.insert(deck.len() - 3, jack);
+        
+        state.deck = deck;
+        state.table_cards.clear();
+        state.player_hands.iter_mut().for_each(Vec::clear);
+        state.deal_initial_cards();
+        
+        assert_ne!(state.table_cards.last().unwrap().rank, Rank::Jack);
+        assert_eq!(state.deck[0], jack);
+        assert_eq!(state.table_hidden, 3);
+        assert!(state.validate_state().is_ok());
+    }
+    
+    #[test]
+    fn test_face_down_cards_go_to_capturer() {
+        let face_down = [
+            Card { suit: Suit::Hearts, rank: Rank::Two },
+            Card { suit: Suit::Hearts, rank: Rank::Three },
+            Card { suit: Suit::Hearts, rank: Rank::Four },
+        ];
+        let top = Card { suit: Suit::Hearts, rank: Rank::Five };
+        let jack = Card { suit: Suit::Spades, rank: Rank::Jack };
+        let table = [face_down[0], face_down[1], face_down[2], top];
+        let mut state = stacked(&table, 3, [
+            &[jack, Card { suit: Suit::Diamonds, rank: Rank::Six }],
+            &[Card { suit: Suit::Clubs, rank: Rank::Six }, Card { suit: Suit::Clubs, rank: Rank::Seven }],
+        ]);
+        assert_eq!(state.visible_table_cards(), &[top]);
+        assert!(state.validate_state().is_ok());
+        
+        let result = state.play_card(PlayerId::PLAYER_ONE, jack).unwrap();
+        
+        assert_eq!(result.hidden_captured, 3);
+        assert_eq!(&result.captured_cards[..3], &face_down[..]);
+        assert_eq!(state.table_hidden, 0);
+        assert_eq!(state.hidden_capture, Some((PlayerId::PLAYER_ONE, 3)));
+        assert!(state.visible_table_cards().is_empty());
+        assert!(state.validate_state().is_ok());
+    }
+    
+    #[test]
+    fn test_player_view_hides_other_hands_and_face_down_captures() {
+        let face_down = [
+            Card { suit: Suit::Hearts, rank: Rank::Two },
+            Card { suit: Suit::Hearts, rank: Rank::Three },
+        ];
+        let top = Card { suit: Suit::Clubs, rank: Rank::Nine };
+        let jack = Card { suit: Suit::Spades, rank: Rank::Jack };
+        let other = Card { suit: Suit::Diamonds, rank: Rank::Four };
+        let table: Vec<Card> = face_down.iter().copied().chain([top]).collect();
+        let mut state = stacked(&table, 2, [&[jack], &[other]]);
+        
+        let view = state.player_view(PlayerId::PLAYER_TWO);
+        assert_eq!(view.table_cards, vec![top]);
+        assert_eq!(view.table_hidden, 2);
+        assert!(view.unseen_cards().contains(&jack));
+        assert!(view.unseen_cards().contains(&face_down[0]));
+        
+        state.play_card(PlayerId::PLAYER_ONE, jack).unwrap();
+        
+        // The capturer saw the face-down cards, the other seat only knows how many there were
+        let capturer = state.player_view(PlayerId::PLAYER_ONE);
+        assert_eq!(capturer.captured[0].len(), 4);
+        let view = state.player_view(PlayerId::PLAYER_TWO);
+        assert_eq!(view.captured[0], vec![top, jack]);
+        assert_eq!(view.captured_hidden, vec![2, 0]);
+        assert!(view.unseen_cards().contains(&face_down[1]));
+    }
+}
@@ -0,0 +1,299 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+/// Longest player name accepted, in characters
+pub const MAX_NAME_LEN: usize = 24;
+/// Largest `max_frame_bytes` an operator may configure
+pub const MAX_FRAME_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Abuse limits applied to every connection
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitConfig {
+    /// Largest text frame handled; bigger ones are refused unread
+    pub max_frame_bytes: usize,
+    /// Messages a connection may send per second on average
+    pub messages_per_sec: f64,
+    /// Messages a connection may send at once before the rate applies
+    pub burst: u32,
+    /// Open connections allowed from one IP address
+    pub max_connections_per_ip: usize,
+    /// Malformed messages after which the address is banned
+    pub max_malformed: u32,
+    pub ban_duration: Duration,
+    /// Reverse proxies whose `X-Forwarded-For` header names the client; empty keys
+    /// everything on the peer address
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl Default for LimitConfig {
+    fn default() -> Self {
+        Self {
+            max_frame_bytes: 16 * 1024,
+            messages_per_sec: 10.0,
+            burst: 20,
+            max_connections_per_ip: 8,
+            max_malformed: 5,
+            ban_duration: Duration::from_secs(300),
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+impl LimitConfig {
+    /// Check that the limits still let a well-behaved client play
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_FRAME_LIMIT).contains(&self.max_frame_bytes) {
+            return Err(format!("max_frame_bytes must be between 1 and {}", MAX_FRAME_LIMIT));
+        }
+        if !self.messages_per_sec.is_finite() || self.messages_per_sec <= 0.0 {
+            return Err("messages_per_sec must be above 0".to_string());
+        }
+        if self.burst == 0 {
+            return Err("burst must be at least 1".to_string());
+        }
+        if self.max_connections_per_ip == 0 {
+            return Err("max_connections_per_ip must be at least 1".to_string());
+        }
+        if self.max_malformed == 0 {
+            return Err("max_malformed must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Address the limits apply to: the peer, or behind a trusted proxy the nearest
+    /// address in `X-Forwarded-For` that is not one of the proxies
+    pub fn client_ip(&self, peer: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        if !self.trusted_proxies.contains(&peer) {
+            return peer;
+        }
+        forwarded_for.into_iter()
+            .flat_map(|header| header.rsplit(','))
+            .map_while(|entry| entry.trim().parse::<IpAddr>().ok())
+            .find(|ip| !self.trusted_proxies.contains(ip))
+            .unwrap_or(peer)
+    }
+}
+
+/// Why the server refused a connection or a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rejection {
+    /// Sending too fast; messages are dropped until a token is free again
+    RateLimited { retry_after_ms: u64 },
+    FrameTooLarge { max_bytes: usize },
+    InvalidName { reason: String },
+    TooManyConnections { max: usize },
+    /// Not valid JSON or not a known message; too many get the address banned
+    MalformedMessage { strikes: u32, max_strikes: u32 },
+    Banned { retry_after_ms: u64 },
+}
+
+/// Trim a player name and check it: not empty, not too long and free of control characters
+pub fn validate_player_name(name: &str) -> Result<String, Rejection> {
+    let name = name.trim();
+    let reason = if name.is_empty() {
+        "Name is empty".to_string()
+    } else if name.chars().count() > MAX_NAME_LEN {
+        format!("Name is longer than {} characters", MAX_NAME_LEN)
+    } else if name.chars().any(|c| c.is_control()) {
+        "Name contains control characters".to_string()
+    } else {
+        return Ok(name.to_string());
+    };
+    Err(Rejection::InvalidName { reason })
+}
+
+/// Token bucket limiting how fast one connection may send
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, refill_per_sec: f64) -> Self {
+        Self {
+            capacity: capacity as f64,
+            refill_per_sec,
+            tokens: capacity as f64,
+            last: Instant::now(),
+        }
+    }
+
+    /// Take a token, or say how long until one is free
+    pub fn try_take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        if self.refill_per_sec <= 0.0 {
+            return Err(Duration::MAX);
+        }
+        Err(Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec))
+    }
+}
+
+/// Connection counts and bans by IP address, shared by all connections
+#[derive(Default)]
+pub struct ConnectionLimits {
+    pub config: LimitConfig,
+    connections: DashMap<IpAddr, usize>,
+    bans: DashMap<IpAddr, Instant>,
+}
+
+/// Counts as one connection from its address until dropped
+pub struct IpPermit {
+    limits: Arc<ConnectionLimits>,
+    ip: IpAddr,
+}
+
+impl Drop for IpPermit {
+    fn drop(&mut self) {
+        self.limits.connections.remove_if_mut(&self.ip, |_, count| {
+            *count -= 1;
+            *count == 0
+        });
+    }
+}
+
+impl ConnectionLimits {
+    pub fn new(config: LimitConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Let a new connection from `ip` in unless it is banned or already at its cap
+    pub fn admit(self: &Arc<Self>, ip: IpAddr) -> Result<IpPermit, Rejection> {
+        if let Some(until) = self.banned_until(ip) {
+            return Err(Rejection::Banned {
+                retry_after_ms: until.saturating_duration_since(Instant::now()).as_millis() as u64,
+            });
+        }
+
+        let mut count = self.connections.entry(ip).or_insert(0);
+        if *count >= self.config.max_connections_per_ip {
+            return Err(Rejection::TooManyConnections { max: self.config.max_connections_per_ip });
+        }
+        *count += 1;
+
+        Ok(IpPermit { limits: self.clone(), ip })
+    }
+
+    pub fn ban(&self, ip: IpAddr) {
+        self.bans.insert(ip, Instant::now() + self.config.ban_duration);
+    }
+
+    /// When the ban on `ip` ends, if it is banned; expired bans are forgotten
+    pub fn banned_until(&self, ip: IpAddr) -> Option<Instant> {
+        let until = *self.bans.get(&ip)?;
+        if until <= Instant::now() {
+            self.bans.remove(&ip);
+            return None;
+        }
+        Some(until)
+    }
+
+    /// A rate limiter for one connection
+    pub fn bucket(&self) -> TokenBucket {
+        TokenBucket::new(self.config.burst, self.config.messages_per_sec)
+    }
+
+    pub fn connections_from(&self, ip: IpAddr) -> usize {
+        self.connections.get(&ip).map_or(0, |count| *count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_player_name() {
+        assert_eq!(validate_player_name("  Alice ").unwrap(), "Alice");
+        assert!(validate_player_name("   ").is_err());
+        assert!(validate_player_name("bad\u{0}name").is_err());
+        assert!(validate_player_name(&"ş".repeat(MAX_NAME_LEN)).is_ok());
+        assert!(matches!(validate_player_name(&"a".repeat(MAX_NAME_LEN + 1)), Err(Rejection::InvalidName { .. })));
+    }
+
+    #[test]
+    fn test_limit_config_is_checked() {
+        assert!(LimitConfig::default().validate().is_ok());
+        let broken = [
+            LimitConfig { messages_per_sec: 0.0, ..Default::default() },
+            LimitConfig { messages_per_sec: f64::NAN, ..Default::default() },
+            LimitConfig { burst: 0, ..Default::default() },
+            LimitConfig { max_frame_bytes: 0, ..Default::default() },
+            LimitConfig { max_frame_bytes: usize::MAX, ..Default::default() },
+            LimitConfig { max_connections_per_ip: 0, ..Default::default() },
+            LimitConfig { max_malformed: 0, ..Default::default() },
+        ];
+        for config in broken {
+            assert!(config.validate().is_err(), "{:?}", config);
+        }
+    }
+
+    #[test]
+    fn test_client_ip_behind_trusted_proxy() {
+        let proxy: IpAddr = [10, 0, 0, 1].into();
+        let config = LimitConfig { trusted_proxies: vec![proxy], ..Default::default() };
+        let client: IpAddr = [203, 0, 113, 7].into();
+
+        // The rightmost address the proxies did not add is the client; earlier ones are the client's word
+        assert_eq!(config.client_ip(proxy, Some("198.51.100.1, 203.0.113.7")), client);
+        assert_eq!(config.client_ip(proxy, Some("203.0.113.7, 10.0.0.1")), client);
+        assert_eq!(config.client_ip(proxy, Some("garbage, 203.0.113.7")), client);
+        assert_eq!(config.client_ip(proxy, Some("garbage")), proxy);
+        assert_eq!(config.client_ip(proxy, None), proxy);
+
+        // Anyone else cannot pick their own address
+        assert_eq!(config.client_ip(client, Some("198.51.100.1")), client);
+        assert_eq!(LimitConfig::default().client_ip(proxy, Some("203.0.113.7")), proxy);
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let mut bucket = TokenBucket::new(3, 1.0);
+        for _ in 0..3 {
+            assert!(bucket.try_take().is_ok());
+        }
+        let wait = bucket.try_take().unwrap_err();
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_ip_caps_and_bans() {
+        let limits = Arc::new(ConnectionLimits::new(LimitConfig {
+            max_connections_per_ip: 2,
+            ..Default::default()
+        }));
+        let ip: IpAddr = [10, 0, 0, 1].into();
+        let other: IpAddr = [10, 0, 0, 2].into();
+
+        let first = limits.admit(ip).unwrap();
+        let _second = limits.admit(ip).unwrap();
+        assert_eq!(limits.admit(ip).err(), Some(Rejection::TooManyConnections { max: 2 }));
+        assert!(limits.admit(other).is_ok());
+
+        // Closing a connection frees its slot
+        drop(first);
+        assert_eq!(limits.connections_from(ip), 1);
+        assert!(limits.admit(ip).is_ok());
+
+        limits.ban(other);
+        assert!(matches!(limits.admit(other), Err(Rejection::Banned { .. })));
+        assert!(limits.banned_until(ip).is_none());
+    }
+}
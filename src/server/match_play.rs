@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::card::PlayerId;
+use crate::server::game_engine::{unique_max, KseriGameState};
+use crate::server::rules::{GameConfig, GameMode};
+
+/// Outcome of one finished deal within a match
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DealResult {
+    pub deal_number: u32,
+    pub seed: u64,
+    pub dealer: PlayerId,
+    /// Deal scores indexed by team
+    pub scores: Vec<u32>,
+}
+
+/// Snapshot of a match in progress, sent to clients with every new deal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchStatus {
+    pub deal_number: u32,
+    pub target_score: u32,
+    pub dealer: PlayerId,
+    /// Cumulative scores indexed by team
+    pub scores: Vec<u32>,
+}
+
+/// A match of several deals played until a team reaches the target score.
+/// The dealer rotates after every deal and the seat after the dealer leads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Match {
+    pub config: GameConfig,
+    pub target_score: u32,
+    /// Cumulative scores indexed by team
+    pub scores: Vec<u32>,
+    pub dealer: PlayerId,
+    pub deals: Vec<DealResult>,
+    /// Seat that gave up, which ends the match
+    #[serde(default)]
+    pub forfeited: Option<PlayerId>,
+}
+
+/// Team that wins when `seat` gives up: the best placed of the others, `None` when they tie
+pub fn forfeit_winner(scores: &[u32], mode: GameMode, seat: PlayerId) -> Option<usize> {
+    let forfeiting_team = mode.team_of(seat.index());
+    let standing: Vec<Option<u32>> = scores.iter().enumerate()
+        .map(|(team, score)| (team != forfeiting_team).then_some(*score))
+        .collect();
+    unique_max(&standing)
+}
+
+impl Match {
+    pub fn new(config: GameConfig, target_score: u32) -> Self {
+        let seats = config.mode.seat_count();
+        
+        Self {
+            config,
+            target_score,
+            scores: vec![0; config.mode.team_count()],
+            // The last seat deals first so seat 0 leads the opening deal
+            dealer: PlayerId((seats - 1) as u8),
+            deals: Vec::new(),
+            forfeited: None,
+        }
+    }
+    
+    /// Number of the deal currently being played, starting at 1
+    pub fn deal_number(&self) -> u32 {
+        self.deals.len() as u32 + 1
+    }
+    
+    pub fn opening_lead(&self) -> PlayerId {
+        self.dealer.next(self.config.mode.seat_count())
+    }
+    
+    /// Deal the next hand of the match
+    pub fn start_deal(&self, seed: u64) -> KseriGameState {
+        KseriGameState::with_opening_lead(seed, self.config, self.opening_lead())
+    }
+    
+    /// Add a finished deal to the running totals and pass the deal to the next seat
+    pub fn record_deal(&mut self, deal: &KseriGameState) -> DealResult {
+        let scores = deal.calculate_scores();
+        for (total, score) in self.scores.iter_mut().zip(&scores) {
+            *total = total.saturating_add(*score);
+        }
+        
+        let result = DealResult {
+            deal_number: self.deal_number(),
+            seed: deal.seed,
+            dealer: self.dealer,
+            scores,
+        };
+        self.deals.push(result.clone());
+        self.dealer = self.dealer.next(self.config.mode.seat_count());
+        
+        result
+    }
+    
+    /// Team that won the match: someone reached the target and holds the outright lead.
+    /// A tie at the top means another deal is played.
+    pub fn winning_team(&self) -> Option<usize> {
+        if let Some(seat) = self.forfeited {
+            return forfeit_winner(&self.scores, self.config.mode, seat);
+        }
+        if self.scores.iter().all(|score| *score < self.target_score) {
+            return None;
+        }
+        
+        unique_max(&self.scores)
+    }
+    
+    pub fn is_over(&self) -> bool {
+        self.forfeited.is_some() || self.winning_team().is_some()
+    }
+    
+    /// End the match because `seat` gave up; the scores so far decide the winner
+    pub fn forfeit(&mut self, seat: PlayerId) {
+        self.forfeited = Some(seat);
+    }
+    
+    pub fn status(&self) -> MatchStatus {
+        MatchStatus {
+            deal_number: self.deal_number(),
+            target_score: self.target_score,
+            dealer: self.dealer,
+            scores: self.scores.clone(),
+        }
+    }
+}
+
+/// Head-to-head results of the games played at one table, across rematches
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeriesTally {
+    /// Games won, indexed by team
+    pub wins: Vec<u32>,
+    pub draws: u32,
+}
+
+impl SeriesTally {
+    pub fn new(team_count: usize) -> Self {
+        Self {
+            wins: vec![0; team_count],
+            draws: 0,
+        }
+    }
+    
+    /// Count a finished game or match; `None` is a draw
+    pub fn record(&mut self, winning_team: Option<usize>) {
+        match winning_team {
+            Some(team) => {
+                if self.wins.len() <= team {
+                    self.wins.resize(team + 1, 0);
+                }
+                self.wins[team] += 1;
+            }
+            None => self.draws += 1,
+        }
+    }
+    
+    pub fn games(&self) -> u32 {
+        self.wins.iter().sum::<u32>() + self.draws
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::rules::GameMode;
+    
+    fn play_out(state: &mut KseriGameState) {
+        while !state.game_over {
+            let player = state.current_turn;
+            let card = state.player_hands[player.index()][0];
+            state.play_card(player, card).unwrap();
+        }
+    }
+    
+    #[test]
+    fn test_dealer_rotates_and_lead_alternates() {
+        let mut game = Match::new(GameConfig::default(), 101);
+        let mut leads = Vec::new();
+        
+        for seed in 0..4 {
+            let mut deal = game.start_deal(seed);
+            leads.push(deal.current_turn);
+            play_out(&mut deal);
+            game.record_deal(&deal);
+        }
+        
+        assert_eq!(leads, vec![PlayerId(0), PlayerId(1), PlayerId(0), PlayerId(1)]);
+        assert_eq!(game.deals.len(), 4);
+        assert_eq!(game.deal_number(), 5);
+        
+        // Cumulative scores are the sum of the deal scores
+        let total: u32 = game.deals.iter().flat_map(|d| d.scores.iter()).sum();
+        assert_eq!(game.scores.iter().sum::<u32>(), total);
+    }
+    
+    #[test]
+    fn test_match_winner_needs_target_and_outright_lead() {
+        let config = GameConfig { mode: GameMode::ThreePlayer, ..Default::default() };
+        let mut game = Match::new(config, 101);
+        assert_eq!(game.opening_lead(), PlayerId(0));
+        
+        game.scores = vec![90, 100, 40];
+        assert!(!game.is_over());
+        
+        game.scores = vec![105, 105, 40];
+        assert_eq!(game.winning_team(), None);
+        
+        game.scores = vec![105, 112, 40];
+        assert_eq!(game.winning_team(), Some(1));
+    }
+}
@@ -1,35 +1,166 @@
+use std::time::{Instant, SystemTime};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::components::card::{Card, PlayerId};
+use crate::server::bot::BotKind;
+use crate::server::chat::Emote;
+use crate::server::commands::NackReason;
+use crate::server::limits::Rejection;
+use crate::server::match_play::{DealResult, MatchStatus, SeriesTally};
+use crate::server::ratings::LeaderboardEntry;
+use crate::server::wire::WireFormat;
+use crate::server::rules::{GameConfig, GameMode, RuleVariant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum ClientMessage {
-    JoinQueue { player_name: String },
-    Reconnect { session_token: String },
+    /// Must be the first message on a connection, see `server::protocol`
+    Hello {
+        protocol_version: u32,
+        client_name: String,
+        /// Names of the `Capability`s the client supports
+        #[serde(default)]
+        capabilities: Vec<String>,
+    },
+    JoinQueue {
+        player_name: String,
+        #[serde(default)]
+        variant: RuleVariant,
+        #[serde(default)]
+        mode: GameMode,
+        /// Play a multi-deal match to this score instead of a single deal
+        #[serde(default)]
+        target_score: Option<u32>,
+        /// Fill the other seats with bots instead of waiting for players
+        #[serde(default)]
+        bot: Option<BotKind>,
+    },
+    /// Open a private table that only players with the returned code can join
+    CreateRoom {
+        player_name: String,
+        #[serde(default)]
+        variant: RuleVariant,
+        #[serde(default)]
+        mode: GameMode,
+        #[serde(default)]
+        target_score: Option<u32>,
+    },
+    /// Take a seat at a private table, see `RoomCreated`
+    JoinRoom {
+        player_name: String,
+        code: String,
+    },
+    /// Play under a rated identity before joining a game. Send the identity from an
+    /// earlier `Identified` to keep your rating, or none to get a new one.
+    Identify {
+        #[serde(default)]
+        identity: Option<String>,
+    },
+    /// Create an account; answered with `LoggedIn`
+    Register {
+        username: String,
+        password: String,
+    },
+    Login {
+        username: String,
+        password: String,
+    },
+    /// Log in again with the token from an earlier `LoggedIn`
+    LoginWithToken {
+        token: String,
+    },
+    RequestLeaderboard {
+        /// Number of entries, 20 when not given
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    Reconnect {
+        session_token: String,
+        /// Last `Sequenced` number received, to get the missed messages replayed
+        #[serde(default)]
+        last_seq: Option<u64>,
+    },
     PlayCard { card: Card },
     RequestState,
+    /// Download the finished game as a text record, see `GameRecord`
+    RequestRecord,
+    /// Offer the rest of the table another game once this one is over
+    RequestRematch,
+    /// Agree to the rematch someone else asked for
+    AcceptRematch,
+    /// Say something to the table, at most `MAX_CHAT_LEN` characters
+    Chat { text: String },
+    Emote { emote: Emote },
+    /// Stop or start showing chat and emotes from `player`
+    Mute { player: PlayerId, muted: bool },
+    /// Watch a game without taking a seat
+    Spectate { session_id: Uuid },
     Ping,
+    /// Switch the messages the server sends to `format`; answered with `WireFormatSet`.
+    /// Text frames are always read as JSON and binary frames with `server::wire`.
+    SetWireFormat {
+        format: WireFormat,
+    },
+    /// Any other message with an id chosen by the client, answered with `Ack` or `Nack`
+    /// carrying the same id. Resending an id already answered gets the same answer
+    /// again without the command being applied twice.
+    Command {
+        id: u64,
+        command: Box<ClientMessage>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum ServerMessage {
+    /// Answer to a supported `Hello`, with the server's `Capability` names
+    Welcome {
+        protocol_version: u32,
+        capabilities: Vec<String>,
+    },
+    /// The client's protocol is not supported, or it did not start with `Hello`;
+    /// the connection is closed after this
+    UnsupportedProtocol {
+        client_version: Option<u32>,
+        min_version: u32,
+        max_version: u32,
+        reason: String,
+    },
     Connected { 
         player_id: PlayerId, 
         session_token: String 
     },
     WaitingForOpponent,
+    /// Invite code of the private table you opened; share it with the other players
+    RoomCreated {
+        code: String,
+    },
     GameStarted { 
+        /// Name of the seat that plays right after you
         opponent_name: String, 
         your_turn: bool,
         initial_hand: Vec<Card>,
+        /// Face-up table cards only
         table_cards: Vec<Card>,
+        /// Face-down cards underneath `table_cards`
+        hidden_table_cards: u8,
+        variant: RuleVariant,
+        mode: GameMode,
+        /// Everyone at the table, indexed by seat
+        player_names: Vec<String>,
+        /// Set when this deal is part of a match
+        match_status: Option<MatchStatus>,
+        /// Ratings indexed by seat; `None` for bots and unrated players
+        #[serde(default)]
+        player_ratings: Vec<Option<u32>>,
     },
     CardPlayed { 
         player: PlayerId, 
         card: Card,
+        /// Captured cards you are allowed to see; the capturer also sees the face-down ones
         captured_cards: Vec<Card>,
+        /// How many face-down table cards were part of the capture
+        hidden_captured: u8,
         is_kseri: bool,
     },
     NewCards {
@@ -37,13 +168,76 @@ pub enum ServerMessage {
     },
     StateUpdate {
         hand_count: u8,
+        /// Hand size of the seat that plays right after you
         opponent_hand_count: u8,
+        /// Face-up table cards only
         table_cards: Vec<Card>,
+        /// Face-down cards underneath `table_cards`
+        hidden_table_cards: u8,
+        /// Your team's score (your own outside partnership play)
         your_score: u32,
+        /// Score of the next seat's team
         opponent_score: u32,
         deck_remaining: u8,
         your_turn: bool,
         last_capture_player: Option<PlayerId>,
+        /// Hand sizes indexed by seat
+        hand_counts: Vec<u8>,
+        /// Scores indexed by team, including the majority bonus
+        team_scores: Vec<u32>,
+        /// Card and kseri points each seat earned, indexed by seat
+        contributions: Vec<u32>,
+        /// Time left for the running turn, when turns are timed
+        #[serde(default)]
+        turn_time_remaining_ms: Option<u64>,
+        /// Game time left per seat, empty without a game clock
+        #[serde(default)]
+        time_banks_ms: Vec<u64>,
+        /// People watching the game
+        #[serde(default)]
+        spectators: u32,
+    },
+    /// The table as a spectator sees it: everything public, no hand contents
+    SpectatorState {
+        player_names: Vec<String>,
+        current_turn: PlayerId,
+        /// Face-up table cards only
+        table_cards: Vec<Card>,
+        /// Face-down cards underneath `table_cards`
+        hidden_table_cards: u8,
+        /// Hand sizes indexed by seat
+        hand_counts: Vec<u8>,
+        deck_remaining: u8,
+        /// Scores indexed by team, including the majority bonus
+        team_scores: Vec<u32>,
+        /// Card and kseri points each seat earned, indexed by seat
+        contributions: Vec<u32>,
+        variant: RuleVariant,
+        mode: GameMode,
+        /// Set when the game is part of a match
+        match_status: Option<MatchStatus>,
+        spectators: u32,
+    },
+    /// Someone started or stopped watching
+    SpectatorsChanged {
+        count: u32,
+    },
+    /// The seat to move is running out of time
+    TimeWarning {
+        player: PlayerId,
+        remaining_ms: u64,
+    },
+    /// The seat ran out of time and the server played for it; `CardPlayed` follows
+    TurnTimedOut {
+        player: PlayerId,
+        /// Consecutive timeouts of this seat
+        timeouts: u32,
+        max_timeouts: u32,
+    },
+    /// The seat timed out too often and lost the game
+    Forfeit {
+        player: PlayerId,
+        winning_team: Option<u8>,
     },
     InvalidMove { 
         reason: String 
@@ -53,29 +247,168 @@ pub enum ServerMessage {
     },
     OpponentReconnected,
     GameOver { 
+        /// Winning seat; always `None` in partnership games, see `winning_team`
         winner: Option<PlayerId>, 
-        final_scores: [u32; 2],
-        captured_cards: [Vec<Card>; 2],
+        winning_team: Option<u8>,
+        /// Final scores indexed by team; each seat is its own team outside partnership play
+        final_scores: Vec<u32>,
+        /// Card and kseri points each seat earned, indexed by seat
+        contributions: Vec<u32>,
+        captured_cards: Vec<Vec<Card>>,
+        seed: u64,
+    },
+    /// A deal of a match finished; the next deal follows unless `MatchOver` does
+    DealSummary {
+        result: DealResult,
+        match_status: MatchStatus,
+    },
+    MatchOver {
+        /// Winning seat; always `None` in partnership games, see `winning_team`
+        winner: Option<PlayerId>,
+        winning_team: u8,
+        match_scores: Vec<u32>,
+        deals: Vec<DealResult>,
+    },
+    Chat {
+        player: PlayerId,
+        name: String,
+        text: String,
+    },
+    Emote {
+        player: PlayerId,
+        emote: Emote,
+    },
+    /// Identity to send with `Identify` from now on, and its current rating
+    Identified {
+        identity: String,
+        rating: u32,
+    },
+    /// A connection or message was refused by the abuse limits, see `Rejection`
+    Rejected {
+        reason: Rejection,
+    },
+    /// Logged into an account; games are played and rated as `username` from now on.
+    /// Keep `token` to log in with `LoginWithToken` on any device.
+    LoggedIn {
+        username: String,
+        identity: String,
+        token: String,
+        rating: u32,
+    },
+    /// A rated game finished and `player`'s rating moved
+    RatingChanged {
+        player: PlayerId,
+        old_rating: u32,
+        new_rating: u32,
+    },
+    Leaderboard {
+        entries: Vec<LeaderboardEntry>,
+    },
+    /// `player` wants a rematch; answer with `AcceptRematch`
+    RematchRequested {
+        player: PlayerId,
+    },
+    /// Results of every game played at this table so far, sent after each one
+    /// and when a rematch starts
+    HeadToHead {
+        tally: SeriesTally,
+    },
+    /// Text record of the finished game, answering `RequestRecord`
+    GameRecord {
+        record: String,
     },
     Error {
         message: String,
     },
     Pong,
+    /// Everything from this message on is sent in `format`
+    WireFormatSet {
+        format: WireFormat,
+    },
+    /// A message for your seat with its sequence number, sent to clients with the
+    /// `resume` capability. Numbers count up by one per seat; drop any already seen.
+    Sequenced {
+        seq: u64,
+        message: Box<ServerMessage>,
+    },
+    /// Snapshot sent on `Reconnect` when the missed messages are no longer buffered.
+    /// `state` is a `StateUpdate`; numbering continues after `seq`.
+    Resync {
+        seq: u64,
+        hand: Vec<Card>,
+        state: Box<ServerMessage>,
+    },
+    /// The `Command` with this id was applied
+    Ack {
+        id: u64,
+    },
+    /// The `Command` with this id was refused and had no effect
+    Nack {
+        id: u64,
+        reason: NackReason,
+    },
+    /// The table closed before it filled and its seats are given up; `code` is set
+    /// for a private room and `None` for a table from the queue
+    RoomClosed {
+        code: Option<String>,
+    },
 }
 
-#[derive(Debug, Clone)]
+/// One entry of a session's append-only event log
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameEvent {
     pub session_id: Uuid,
+    /// Position in the session's log, starting at 0
+    pub sequence: u64,
     pub event_type: GameEventType,
-    pub timestamp: std::time::Instant,
+    /// Monotonic time, for measuring intervals; reset when a session is restored
+    #[serde(skip, default = "Instant::now")]
+    pub timestamp: Instant,
+    /// Wall-clock time the event was recorded
+    pub recorded_at: SystemTime,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameEventType {
     PlayerJoined(PlayerId, String),
     PlayerDisconnected(PlayerId),
     PlayerReconnected(PlayerId),
-    CardPlayed(PlayerId, Card),
-    CardsDealt,
+    /// A deal began; together with the plays after it this rebuilds the game
+    DealStarted {
+        seed: u64,
+        config: GameConfig,
+        opening_lead: PlayerId,
+    },
+    CardPlayed {
+        player: PlayerId,
+        card: Card,
+        /// Everything the play took from the table, face-down cards included
+        captured: Vec<Card>,
+        is_kseri: bool,
+    },
+    /// Every hand ran out and a fresh round was dealt
+    CardsDealt {
+        hands: Vec<Vec<Card>>,
+        deck_remaining: usize,
+    },
+    /// A deal finished; in a match another `DealStarted` may follow
+    DealEnded {
+        winning_team: Option<usize>,
+        /// Deal scores indexed by team
+        scores: Vec<u32>,
+    },
+    /// The game or match is over
     GameEnded(Option<PlayerId>),
-}
\ No newline at end of file
+    Chat {
+        player: PlayerId,
+        text: String,
+    },
+    Emote {
+        player: PlayerId,
+        emote: Emote,
+    },
+    /// `player` gave up, ending the deal and any match where they stand
+    Forfeited {
+        player: PlayerId,
+    },
+}
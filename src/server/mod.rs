@@ -3,9 +3,41 @@ pub mod session;
 pub mod messages;
 pub mod game_engine;
 pub mod broadcaster;
+pub mod rules;
+pub mod match_play;
+pub mod bot;
+pub mod search;
+pub mod record;
+pub mod persistence;
+pub mod event_log;
+pub mod clock;
+pub mod chat;
+pub mod ratings;
+pub mod accounts;
+pub mod limits;
+pub mod wire;
+pub mod protocol;
+pub mod resume;
+pub mod commands;
 
 pub use connection::*;
 pub use session::*;
 pub use messages::*;
 pub use game_engine::*;
-pub use broadcaster::*;
\ No newline at end of file
+pub use broadcaster::*;
+pub use rules::*;
+pub use match_play::*;
+pub use bot::*;
+pub use search::*;
+pub use record::*;
+pub use persistence::*;
+pub use event_log::*;
+pub use clock::*;
+pub use chat::*;
+pub use ratings::*;
+pub use accounts::*;
+pub use limits::*;
+pub use wire::WireFormat;
+pub use protocol::*;
+pub use resume::*;
+pub use commands::*;
\ No newline at end of file
@@ -0,0 +1,159 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::server::event_log::GameLog;
+use crate::server::game_engine::KseriGameState;
+use crate::server::match_play::{Match, SeriesTally};
+use crate::server::session::Player;
+
+/// Everything needed to bring a `GameSession` back after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub id: Uuid,
+    /// One slot per seat, indexed by `PlayerId`
+    pub players: Vec<Option<Player>>,
+    pub game_state: KseriGameState,
+    pub match_play: Option<Match>,
+    /// How old the session was when the snapshot was taken
+    pub age_secs: u64,
+    #[serde(default)]
+    pub events: GameLog,
+    #[serde(default)]
+    pub tally: SeriesTally,
+}
+
+/// Session snapshots stored as one JSON file per session in a directory
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    /// Use `dir` for snapshots, creating it if needed
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, session_id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.json", session_id))
+    }
+
+    /// Replace the session's snapshot atomically: write a temporary file, sync it, then rename it over the old one
+    pub async fn save(&self, snapshot: &SessionSnapshot) -> io::Result<()> {
+        let json = serde_json::to_vec(snapshot)?;
+        let tmp = self.dir.join(format!("{}.{}.tmp", snapshot.id, Uuid::new_v4()));
+
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        file.write_all(&json).await?;
+        file.sync_all().await?;
+        drop(file);
+
+        if let Err(e) = tokio::fs::rename(&tmp, self.path(snapshot.id)).await {
+            tokio::fs::remove_file(&tmp).await.ok();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub async fn remove(&self, session_id: Uuid) -> io::Result<()> {
+        match tokio::fs::remove_file(self.path(session_id)).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Every readable snapshot; unreadable ones are skipped and half-written temporary files removed
+    pub fn load_all(&self) -> io::Result<Vec<SessionSnapshot>> {
+        let mut snapshots = Vec::new();
+
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => {
+                    let loaded = std::fs::read(&path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| e.to_string()));
+                    match loaded {
+                        Ok(snapshot) => snapshots.push(snapshot),
+                        Err(e) => warn!("Skipping unreadable session snapshot {}: {}", path.display(), e),
+                    }
+                }
+                Some("tmp") => {
+                    std::fs::remove_file(&path).ok();
+                }
+                _ => {}
+            }
+        }
+
+        Ok(snapshots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::card::PlayerId;
+    use crate::server::bot::BotKind;
+    use crate::server::session::GameSession;
+    use crate::server::rules::GameConfig;
+
+    fn temp_store() -> SessionStore {
+        SessionStore::new(std::env::temp_dir().join(format!("kseri-store-{}", Uuid::new_v4()))).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let store = temp_store();
+        let session = GameSession::with_seed(3, GameConfig::default());
+        let (player_id, token) = session.add_player(Uuid::new_v4(), "A".to_string()).unwrap();
+        session.fill_with_bots(BotKind::Heuristic);
+        {
+            let mut state = session.game_state.write().await;
+            let card = state.player_hands[0][0];
+            state.play_card(player_id, card).unwrap();
+        }
+
+        store.save(&session.snapshot().await).await.unwrap();
+        // Saving again replaces the file rather than adding another
+        store.save(&session.snapshot().await).await.unwrap();
+
+        let snapshots = store.load_all().unwrap();
+        assert_eq!(snapshots.len(), 1);
+        let restored = GameSession::from_snapshot(snapshots[0].clone());
+        assert_eq!(restored.id, session.id);
+        assert_eq!(restored.game_state.read().await.move_history, session.game_state.read().await.move_history);
+
+        // Everyone starts out disconnected with a fresh reconnect window; bots stay bots
+        let human = restored.get_player_by_token(&token).unwrap();
+        assert!(!human.connected && human.disconnected_at.is_some());
+        assert_eq!(restored.players()[1].as_ref().unwrap().bot, Some(BotKind::Heuristic));
+        assert_eq!(restored.players()[1].as_ref().unwrap().player_id, PlayerId::PLAYER_TWO);
+
+        store.remove(session.id).await.unwrap();
+        store.remove(session.id).await.unwrap();
+        assert!(store.load_all().unwrap().is_empty());
+        std::fs::remove_dir_all(store.dir()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_and_partial_files_are_skipped() {
+        let store = temp_store();
+        std::fs::write(store.dir().join(format!("{}.json", Uuid::new_v4())), b"{ not json").unwrap();
+        let tmp = store.dir().join(format!("{}.{}.tmp", Uuid::new_v4(), Uuid::new_v4()));
+        std::fs::write(&tmp, b"{").unwrap();
+
+        assert!(store.load_all().unwrap().is_empty());
+        assert!(!tmp.exists());
+        std::fs::remove_dir_all(store.dir()).ok();
+    }
+}
@@ -0,0 +1,111 @@
+//! Protocol versions and optional features, agreed on with `Hello` and `Welcome`.
+//!
+//! Compatibility policy: adding a message, an optional (`#[serde(default)]`) field or a
+//! capability keeps the version. Anything that breaks older clients bumps
+//! `PROTOCOL_VERSION`: removing or renaming a message or field, changing a field's type,
+//! or inserting variants before existing ones, since the binary format sends them by index.
+//! `MIN_PROTOCOL_VERSION` is raised once the server stops accepting older clients.
+
+/// Version of the message protocol this server speaks
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Oldest client version still accepted
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Optional features, sent by name so either side can list ones the other does not know
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    Spectate,
+    Chat,
+    /// Rule variants other than classic Kseri
+    Variants,
+    /// Games played over several deals to a target score
+    Matches,
+    Rooms,
+    Rematch,
+    Ratings,
+    Accounts,
+    BinaryWire,
+    /// Turns are timed; only when the server has a turn clock
+    TurnClock,
+    /// Numbered messages and replay on `Reconnect`; clients opt in by listing it in `Hello`
+    Resume,
+    /// `Command` envelopes answered with `Ack` or `Nack`
+    Commands,
+}
+
+impl Capability {
+    pub fn all() -> [Capability; 12] {
+        [
+            Capability::Spectate,
+            Capability::Chat,
+            Capability::Variants,
+            Capability::Matches,
+            Capability::Rooms,
+            Capability::Rematch,
+            Capability::Ratings,
+            Capability::Accounts,
+            Capability::BinaryWire,
+            Capability::TurnClock,
+            Capability::Resume,
+            Capability::Commands,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::Spectate => "spectate",
+            Capability::Chat => "chat",
+            Capability::Variants => "variants",
+            Capability::Matches => "matches",
+            Capability::Rooms => "rooms",
+            Capability::Rematch => "rematch",
+            Capability::Ratings => "ratings",
+            Capability::Accounts => "accounts",
+            Capability::BinaryWire => "binary_wire",
+            Capability::TurnClock => "turn_clock",
+            Capability::Resume => "resume",
+            Capability::Commands => "commands",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Capability> {
+        Capability::all().into_iter().find(|capability| capability.name() == name)
+    }
+}
+
+/// Why a client's protocol version is refused, or `None` when it is supported
+pub fn check_version(client_version: u32) -> Option<String> {
+    if client_version < MIN_PROTOCOL_VERSION {
+        Some(format!(
+            "Client protocol {} is too old, this server needs {} to {}; please update the client",
+            client_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+        ))
+    } else if client_version > PROTOCOL_VERSION {
+        Some(format!(
+            "Client protocol {} is newer than this server's {} to {}",
+            client_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+        ))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_version() {
+        assert!(check_version(PROTOCOL_VERSION).is_none());
+        assert!(check_version(MIN_PROTOCOL_VERSION - 1).unwrap().contains("too old"));
+        assert!(check_version(PROTOCOL_VERSION + 1).unwrap().contains("newer"));
+    }
+
+    #[test]
+    fn test_capability_names() {
+        for capability in Capability::all() {
+            assert_eq!(Capability::from_name(capability.name()), Some(capability));
+        }
+        assert_eq!(Capability::from_name("teleport"), None);
+    }
+}
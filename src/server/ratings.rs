@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+/// Rating every new player starts with
+pub const INITIAL_RATING: f64 = 1500.0;
+/// Largest rating change a single game can cause
+pub const K_FACTOR: f64 = 32.0;
+/// Most entries a leaderboard request may ask for
+pub const MAX_LEADERBOARD: usize = 100;
+
+/// Elo rating and record of one player identity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    /// Name the player used most recently
+    pub name: String,
+    pub rating: f64,
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Rating {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            rating: INITIAL_RATING,
+            games: 0,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        }
+    }
+
+    /// Rating as shown to players
+    pub fn display(&self) -> u32 {
+        self.rating.round().max(0.0) as u32
+    }
+}
+
+/// One row of the leaderboard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub rank: u32,
+    pub name: String,
+    pub rating: u32,
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+/// Chance that a player rated `rating` beats one rated `opponent`
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// Ratings by player identity, kept in a JSON file when a path is given
+#[derive(Default)]
+pub struct RatingStore {
+    path: Option<PathBuf>,
+    ratings: RwLock<HashMap<String, Rating>>,
+    /// Held from serializing until the file is replaced, so saves land in the order they were taken
+    saving: Mutex<()>,
+}
+
+impl RatingStore {
+    /// Ratings that only live as long as the process
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Load the ratings saved at `path`, starting empty when the file does not exist yet
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let ratings = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            path: Some(path),
+            ratings: RwLock::new(ratings),
+            saving: Mutex::new(()),
+        })
+    }
+
+    /// Make sure `identity` has a rating and remember the name it plays under
+    pub async fn register(&self, identity: &str, name: &str) {
+        let mut ratings = self.ratings.write().await;
+        ratings.entry(identity.to_string())
+            .and_modify(|rating| rating.name = name.to_string())
+            .or_insert_with(|| Rating::new(name.to_string()));
+    }
+
+    pub async fn get(&self, identity: &str) -> Option<Rating> {
+        self.ratings.read().await.get(identity).cloned()
+    }
+
+    /// Rate a finished game. `seats` holds each seat's identity and team; every pair of
+    /// seats on different teams counts as a game between the two, and each player's change
+    /// is averaged over their opponents. Returns the new ratings in seat order.
+    pub async fn record_game(&self, seats: &[(String, usize)], winning_team: Option<usize>) -> io::Result<Vec<Rating>> {
+        let mut ratings = self.ratings.write().await;
+        let before: Vec<f64> = seats.iter()
+            .map(|(identity, _)| ratings.get(identity).map_or(INITIAL_RATING, |rating| rating.rating))
+            .collect();
+
+        let mut updated = Vec::with_capacity(seats.len());
+        for (idx, (identity, team)) in seats.iter().enumerate() {
+            let opponents: Vec<usize> = (0..seats.len()).filter(|other| seats[*other].1 != *team).collect();
+            let change: f64 = opponents.iter()
+                .map(|other| {
+                    let score = match winning_team {
+                        Some(winner) if winner == *team => 1.0,
+                        Some(winner) if winner == seats[*other].1 => 0.0,
+                        _ => 0.5,
+                    };
+                    K_FACTOR * (score - expected_score(before[idx], before[*other]))
+                })
+                .sum::<f64>() / opponents.len().max(1) as f64;
+
+            let rating = ratings.entry(identity.clone()).or_insert_with(|| Rating::new(String::new()));
+            rating.rating += change;
+            rating.games += 1;
+            match winning_team {
+                Some(winner) if winner == *team => rating.wins += 1,
+                Some(_) => rating.losses += 1,
+                None => rating.draws += 1,
+            }
+            updated.push(rating.clone());
+        }
+
+        // Queue for the file before letting other updates in, so a newer snapshot is never
+        // overwritten by an older one
+        let saving = self.saving.lock().await;
+        let json = serde_json::to_vec(&*ratings)?;
+        drop(ratings);
+        self.save(json).await?;
+        drop(saving);
+
+        Ok(updated)
+    }
+
+    /// The `limit` best-rated players who have finished at least one rated game
+    pub async fn leaderboard(&self, limit: usize) -> Vec<LeaderboardEntry> {
+        let ratings = self.ratings.read().await;
+        let mut rated: Vec<&Rating> = ratings.values().filter(|rating| rating.games > 0).collect();
+        rated.sort_by(|a, b| b.rating.total_cmp(&a.rating).then_with(|| a.name.cmp(&b.name)));
+
+        rated.into_iter()
+            .take(limit.min(MAX_LEADERBOARD))
+            .enumerate()
+            .map(|(idx, rating)| LeaderboardEntry {
+                rank: idx as u32 + 1,
+                name: rating.name.clone(),
+                rating: rating.display(),
+                games: rating.games,
+                wins: rating.wins,
+                losses: rating.losses,
+                draws: rating.draws,
+            })
+            .collect()
+    }
+
+    /// Replace the ratings file atomically, like `SessionStore::save`
+    async fn save(&self, json: Vec<u8>) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let tmp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        file.write_all(&json).await?;
+        file.sync_all().await?;
+        drop(file);
+
+        if let Err(e) = tokio::fs::rename(&tmp, path).await {
+            tokio::fs::remove_file(&tmp).await.ok();
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_score() {
+        assert_eq!(expected_score(1500.0, 1500.0), 0.5);
+        assert!((expected_score(1900.0, 1500.0) - 0.909).abs() < 0.001);
+    }
+
+    #[tokio::test]
+    async fn test_ratings_survive_reopening() {
+        let path = std::env::temp_dir().join(format!("kseri-ratings-{}.json", Uuid::new_v4()));
+        let store = RatingStore::open(&path).unwrap();
+        store.register("a", "Alice").await;
+        store.register("b", "Bob").await;
+
+        let seats = vec![("a".to_string(), 0), ("b".to_string(), 1)];
+        let updated = store.record_game(&seats, Some(0)).await.unwrap();
+        assert_eq!(updated[0].rating, INITIAL_RATING + K_FACTOR / 2.0);
+        assert_eq!(updated[1].rating, INITIAL_RATING - K_FACTOR / 2.0);
+
+        // A draw between unequal players moves them towards each other
+        let updated = store.record_game(&seats, None).await.unwrap();
+        assert!(updated[0].rating < INITIAL_RATING + K_FACTOR / 2.0);
+        assert_eq!(updated[1].draws, 1);
+
+        let reopened = RatingStore::open(&path).unwrap();
+        let board = reopened.leaderboard(10).await;
+        assert_eq!(board.len(), 2);
+        assert_eq!((board[0].rank, board[0].name.as_str(), board[0].wins), (1, "Alice", 1));
+        assert_eq!(reopened.get("b").await.unwrap().games, 2);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_games_are_all_saved() {
+        let path = std::env::temp_dir().join(format!("kseri-ratings-{}.json", Uuid::new_v4()));
+        let store = RatingStore::open(&path).unwrap();
+        let games: Vec<Vec<(String, usize)>> = (0..8)
+            .map(|game| vec![(format!("a{}", game), 0), (format!("b{}", game), 1)])
+            .collect();
+
+        futures_util::future::join_all(games.iter().map(|seats| store.record_game(seats, Some(0)))).await;
+
+        // The file holds every game, whichever save finished last
+        let reopened = RatingStore::open(&path).unwrap();
+        assert_eq!(reopened.leaderboard(MAX_LEADERBOARD).await.len(), 16);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_partnership_rating() {
+        let store = RatingStore::in_memory();
+        let seats: Vec<(String, usize)> = ["a", "b", "c", "d"].iter().enumerate()
+            .map(|(seat, id)| (id.to_string(), seat % 2))
+            .collect();
+
+        let updated = store.record_game(&seats, Some(1)).await.unwrap();
+        // Partners gain and lose together
+        assert_eq!(updated[0].rating, updated[2].rating);
+        assert_eq!(updated[1].rating, INITIAL_RATING + K_FACTOR / 2.0);
+        assert!(store.leaderboard(0).await.is_empty());
+    }
+}
@@ -0,0 +1,523 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::components::card::{Card, PlayerId, Rank, Suit};
+use crate::server::game_engine::KseriGameState;
+use crate::server::rules::{GameConfig, GameMode, RuleSet, RuleVariant};
+
+/// One play of a recorded game, annotated with what it did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordedPlay {
+    pub player: PlayerId,
+    pub card: Card,
+    /// Cards taken from the table including the played card, 0 when nothing was captured
+    pub captured: usize,
+    pub is_kseri: bool,
+}
+
+/// A finished game in a portable text format, in the spirit of PGN for chess.
+///
+/// ```text
+/// [Variant "Classic"]
+/// [Mode "HeadsUp"]
+/// [Seed "42"]
+/// [Lead "P1"]
+/// [Date "2026.10.17"]
+/// [P1 "Alice"]
+/// [P2 "Bob"]
+/// [Result "17-9"]
+///
+/// 1. P1 7H
+/// 2. P2 7D x2 *
+/// 3. P1 JS x3
+/// ```
+///
+/// Seats are numbered from 1, cards are a rank (`A`, `2`-`10`, `J`, `Q`, `K`) followed by a suit
+/// letter (`C`, `D`, `H`, `S`). `xN` marks a capture of N cards and `*` a kseri.
+/// A game someone gave up carries a `[Forfeit "P2"]` tag after the result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    pub config: GameConfig,
+    pub seed: u64,
+    pub opening_lead: PlayerId,
+    /// Day the game finished, `YYYY.MM.DD`
+    pub date: String,
+    /// Player names indexed by seat
+    pub players: Vec<String>,
+    /// Final scores indexed by team
+    pub result: Vec<u32>,
+    /// Seat that gave up before the cards ran out
+    pub forfeited: Option<PlayerId>,
+    pub plays: Vec<RecordedPlay>,
+}
+
+impl GameRecord {
+    /// Record a finished game from its seed and move history
+    pub fn from_game(state: &KseriGameState, players: Vec<String>, date: String) -> Result<Self, String> {
+        if !state.game_over {
+            return Err("Game is not over yet".to_string());
+        }
+
+        // The move history does not keep captures, so play it again to annotate each move
+        let mut replay = KseriGameState::with_opening_lead(state.seed, state.config, state.opening_lead);
+        let mut plays = Vec::with_capacity(state.move_history.len());
+        for (player, card) in &state.move_history {
+            let result = replay.play_card(*player, *card).map_err(|e| e.to_string())?;
+            plays.push(RecordedPlay {
+                player: *player,
+                card: *card,
+                captured: result.captured_cards.len(),
+                is_kseri: result.is_kseri,
+            });
+        }
+
+        Ok(Self {
+            config: GameConfig { target_score: None, ..state.config },
+            seed: state.seed,
+            opening_lead: state.opening_lead,
+            date,
+            players,
+            result: state.calculate_scores(),
+            forfeited: state.forfeited,
+            plays,
+        })
+    }
+
+    /// Replay the record through the game engine, checking every move, annotation and the result.
+    /// Returns the game after every move; the first is the opening deal, the last the finished game.
+    pub fn replay(&self) -> Result<Vec<KseriGameState>, String> {
+        if self.opening_lead.index() >= self.config.mode.seat_count() {
+            return Err(format!("Lead {} is not a seat in {:?}", seat_code(self.opening_lead), self.config.mode));
+        }
+
+        let mut state = KseriGameState::with_opening_lead(self.seed, self.config, self.opening_lead);
+        let mut positions = Vec::with_capacity(self.plays.len() + 1);
+        positions.push(state.clone());
+
+        for (idx, play) in self.plays.iter().enumerate() {
+            let move_number = idx + 1;
+            let result = state.play_card(play.player, play.card)
+                .map_err(|e| format!("Move {} ({}): {}", move_number, format_play(play), e))?;
+
+            if result.captured_cards.len() != play.captured || result.is_kseri != play.is_kseri {
+                return Err(format!(
+                    "Move {} ({}): recorded x{}{} but the play captured {}{}",
+                    move_number,
+                    format_play(play),
+                    play.captured,
+                    if play.is_kseri { " *" } else { "" },
+                    result.captured_cards.len(),
+                    if result.is_kseri { " with a kseri" } else { "" },
+                ));
+            }
+            positions.push(state.clone());
+        }
+
+        // The forfeit ends the game at the last move rather than adding a position
+        let state = positions.last_mut().expect("the opening deal is always there");
+        if let Some(seat) = self.forfeited {
+            state.forfeit(seat);
+        }
+        if !state.game_over {
+            return Err(format!("Record ends after {} moves, before the game is over", self.plays.len()));
+        }
+        let scores = state.calculate_scores();
+        if scores != self.result {
+            return Err(format!("Recorded result {} but the game scored {}", join_scores(&self.result), join_scores(&scores)));
+        }
+
+        Ok(positions)
+    }
+
+    /// Parse a record written by `Display`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut variant = None;
+        let mut rules = None;
+        let mut mode = None;
+        let mut seed = None;
+        let mut opening_lead = PlayerId::PLAYER_ONE;
+        let mut date = String::new();
+        let mut players = Vec::new();
+        let mut result = None;
+        let mut forfeited = None;
+        let mut plays = Vec::new();
+
+        for (line_idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let line_number = line_idx + 1;
+            let err = |message: String| format!("Line {}: {}", line_number, message);
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(tag) = line.strip_prefix('[') {
+                let (name, value) = parse_tag(tag).ok_or_else(|| err(format!("Malformed tag '{}'", line)))?;
+                match name {
+                    "Variant" => variant = Some(value.to_string()),
+                    "Rules" => rules = Some(parse_rules(value).map_err(err)?),
+                    "Mode" => mode = Some(parse_mode(value).map_err(err)?),
+                    "Seed" => seed = Some(value.parse().map_err(|_| err(format!("Invalid seed '{}'", value)))?),
+                    "Lead" => opening_lead = parse_seat(value).map_err(err)?,
+                    "Date" => date = value.to_string(),
+                    "Result" => result = Some(parse_scores(value).map_err(err)?),
+                    "Forfeit" => forfeited = Some(parse_seat(value).map_err(err)?),
+                    seat if parse_seat(seat).is_ok() => {
+                        let seat = parse_seat(seat).map_err(err)?.index();
+                        if players.len() <= seat {
+                            players.resize(seat + 1, String::new());
+                        }
+                        players[seat] = value.to_string();
+                    }
+                    // Unknown tags are kept by other tools; ignore them
+                    _ => {}
+                }
+            } else {
+                plays.push(parse_play(line, plays.len() + 1).map_err(err)?);
+            }
+        }
+
+        let variant = match (variant.as_deref(), rules) {
+            (Some("Custom"), Some(rules)) => RuleVariant::Custom(rules),
+            (Some("Custom"), None) => return Err("Custom variant without a Rules tag".to_string()),
+            (Some(name), _) => parse_variant(name)?,
+            (None, _) => RuleVariant::default(),
+        };
+        let mode = mode.unwrap_or_default();
+        if players.len() > mode.seat_count() {
+            return Err(format!("{} players named for {:?}", players.len(), mode));
+        }
+        players.resize(mode.seat_count(), String::new());
+
+        Ok(Self {
+            config: GameConfig { variant, mode, target_score: None },
+            seed: seed.ok_or("Missing Seed tag")?,
+            opening_lead,
+            date,
+            players,
+            result: result.ok_or("Missing Result tag")?,
+            forfeited,
+            plays,
+        })
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.config.variant {
+            RuleVariant::Custom(rules) => {
+                writeln!(f, "[Variant \"Custom\"]")?;
+                writeln!(f, "[Rules \"{}\"]", format_rules(&rules))?;
+            }
+            variant => writeln!(f, "[Variant \"{:?}\"]", variant)?,
+        }
+        writeln!(f, "[Mode \"{:?}\"]", self.config.mode)?;
+        writeln!(f, "[Seed \"{}\"]", self.seed)?;
+        writeln!(f, "[Lead \"{}\"]", seat_code(self.opening_lead))?;
+        writeln!(f, "[Date \"{}\"]", self.date)?;
+        for (seat, name) in self.players.iter().enumerate() {
+            // Quotes and line breaks would end the tag early
+            let name = name.replace(|c: char| c == '"' || c.is_control(), "'");
+            writeln!(f, "[{} \"{}\"]", seat_code(PlayerId(seat as u8)), name)?;
+        }
+        writeln!(f, "[Result \"{}\"]", join_scores(&self.result))?;
+        if let Some(seat) = self.forfeited {
+            writeln!(f, "[Forfeit \"{}\"]", seat_code(seat))?;
+        }
+        writeln!(f)?;
+
+        for (idx, play) in self.plays.iter().enumerate() {
+            writeln!(f, "{}. {}", idx + 1, format_play(play))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Today's date in UTC, `YYYY.MM.DD`
+pub fn record_date() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Compact card notation: rank then suit letter, e.g. `10D` or `JS`
+pub fn card_code(card: Card) -> String {
+    let suit = match card.suit {
+        Suit::Clubs => 'C',
+        Suit::Diamonds => 'D',
+        Suit::Hearts => 'H',
+        Suit::Spades => 'S',
+    };
+    format!("{}{}", card.rank.symbol(), suit)
+}
+
+pub fn parse_card(code: &str) -> Result<Card, String> {
+    let invalid = || format!("Invalid card '{}'", code);
+    let (split, _) = code.char_indices().last().ok_or_else(invalid)?;
+    let (rank, suit) = code.split_at(split);
+
+    let suit = match suit {
+        "C" => Suit::Clubs,
+        "D" => Suit::Diamonds,
+        "H" => Suit::Hearts,
+        "S" => Suit::Spades,
+        _ => return Err(invalid()),
+    };
+    let rank = Rank::all().into_iter()
+        .find(|r| r.symbol() == rank)
+        .ok_or_else(invalid)?;
+
+    Ok(Card::new(suit, rank))
+}
+
+fn seat_code(seat: PlayerId) -> String {
+    format!("P{}", seat.index() + 1)
+}
+
+fn parse_seat(code: &str) -> Result<PlayerId, String> {
+    code.strip_prefix('P')
+        .and_then(|n| n.parse::<u8>().ok())
+        .filter(|n| (1..=4).contains(n))
+        .map(|n| PlayerId(n - 1))
+        .ok_or_else(|| format!("Invalid seat '{}'", code))
+}
+
+fn format_play(play: &RecordedPlay) -> String {
+    let mut text = format!("{} {}", seat_code(play.player), card_code(play.card));
+    if play.captured > 0 {
+        text.push_str(&format!(" x{}", play.captured));
+    }
+    if play.is_kseri {
+        text.push_str(" *");
+    }
+    text
+}
+
+/// `N. P1 7D x2 *`, where the move number must be `expected`
+fn parse_play(line: &str, expected: usize) -> Result<RecordedPlay, String> {
+    let mut parts = line.split_whitespace();
+
+    let number = parts.next().and_then(|n| n.strip_suffix('.')).and_then(|n| n.parse::<usize>().ok());
+    if number != Some(expected) {
+        return Err(format!("Expected move {}, found '{}'", expected, line));
+    }
+    let player = parse_seat(parts.next().ok_or("Missing seat")?)?;
+    let card = parse_card(parts.next().ok_or("Missing card")?)?;
+
+    let mut play = RecordedPlay { player, card, captured: 0, is_kseri: false };
+    for annotation in parts {
+        match annotation {
+            "*" => play.is_kseri = true,
+            _ => {
+                play.captured = annotation.strip_prefix('x')
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(|| format!("Invalid annotation '{}'", annotation))?;
+            }
+        }
+    }
+
+    Ok(play)
+}
+
+/// `Name "value"]` after the opening bracket
+fn parse_tag(tag: &str) -> Option<(&str, &str)> {
+    let (name, rest) = tag.strip_suffix(']')?.split_once(' ')?;
+    let value = rest.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name, value))
+}
+
+fn parse_variant(name: &str) -> Result<RuleVariant, String> {
+    RuleVariant::all().into_iter()
+        .find(|variant| format!("{:?}", variant) == name)
+        .ok_or_else(|| format!("Unknown variant '{}'", name))
+}
+
+fn parse_mode(name: &str) -> Result<GameMode, String> {
+    GameMode::all().into_iter()
+        .find(|mode| format!("{:?}", mode) == name)
+        .ok_or_else(|| format!("Unknown mode '{}'", name))
+}
+
+fn join_scores(scores: &[u32]) -> String {
+    scores.iter().map(u32::to_string).collect::<Vec<_>>().join("-")
+}
+
+fn parse_scores(text: &str) -> Result<Vec<u32>, String> {
+    text.split('-')
+        .map(|score| score.parse().map_err(|_| format!("Invalid result '{}'", text)))
+        .collect()
+}
+
+/// Custom point tables as `key=value` pairs; a missing `jack_kseri` means Jack-on-Jack is a plain sweep
+fn format_rules(rules: &RuleSet) -> String {
+    let mut text = format!(
+        "ace={} jack={} jack_of_diamonds={} two_of_clubs={} ten_of_diamonds={} kseri={} majority={}",
+        rules.ace_points,
+        rules.jack_points,
+        rules.jack_of_diamonds_points,
+        rules.two_of_clubs_points,
+        rules.ten_of_diamonds_points,
+        rules.kseri_bonus,
+        rules.majority_bonus,
+    );
+    if let Some(bonus) = rules.jack_kseri_bonus {
+        text.push_str(&format!(" jack_kseri={}", bonus));
+    }
+    text
+}
+
+fn parse_rules(text: &str) -> Result<RuleSet, String> {
+    let mut rules = RuleVariant::Classic.rules();
+    rules.jack_kseri_bonus = None;
+
+    for pair in text.split_whitespace() {
+        let (key, value) = pair.split_once('=').ok_or_else(|| format!("Invalid rule '{}'", pair))?;
+        let value: u32 = value.parse().map_err(|_| format!("Invalid rule '{}'", pair))?;
+        match key {
+            "ace" => rules.ace_points = value,
+            "jack" => rules.jack_points = value,
+            "jack_of_diamonds" => rules.jack_of_diamonds_points = value,
+            "two_of_clubs" => rules.two_of_clubs_points = value,
+            "ten_of_diamonds" => rules.ten_of_diamonds_points = value,
+            "kseri" => rules.kseri_bonus = value,
+            "jack_kseri" => rules.jack_kseri_bonus = Some(value),
+            "majority" => rules.majority_bonus = value,
+            _ => return Err(format!("Unknown rule '{}'", key)),
+        }
+    }
+
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished_game(seed: u64, config: GameConfig) -> KseriGameState {
+        let mut state = KseriGameState::with_config(seed, config);
+        while !state.game_over {
+            let player = state.current_turn;
+            let card = state.player_hands[player.index()][0];
+            state.play_card(player, card).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_card_codes_round_trip() {
+        for suit in Suit::all() {
+            for rank in Rank::all() {
+                let card = Card::new(suit, rank);
+                assert_eq!(parse_card(&card_code(card)), Ok(card));
+            }
+        }
+        assert_eq!(card_code(Card::new(Suit::Diamonds, Rank::Ten)), "10D");
+        assert!(parse_card("1D").is_err());
+        assert!(parse_card("").is_err());
+    }
+
+    #[test]
+    fn test_record_round_trips_and_replays() {
+        for mode in GameMode::all() {
+            let state = finished_game(21, GameConfig { mode, ..Default::default() });
+            let names = (0..mode.seat_count()).map(|seat| format!("Player \"{}\"", seat)).collect();
+            let record = GameRecord::from_game(&state, names, "2026.10.17".to_string()).unwrap();
+
+            let text = record.to_string();
+            let parsed = GameRecord::parse(&text).unwrap();
+            assert_eq!(parsed.plays, record.plays);
+            assert_eq!(parsed.players[0], "Player '0'");
+            assert_eq!(parsed.config.mode, mode);
+
+            let positions = parsed.replay().unwrap();
+            assert_eq!(positions.len(), record.plays.len() + 1);
+            assert!(positions[0].move_history.is_empty());
+            let replayed = positions.last().unwrap();
+            assert_eq!(replayed.player_scores, state.player_scores);
+            assert_eq!(replayed.move_history, state.move_history);
+        }
+    }
+
+    #[test]
+    fn test_custom_rules_survive_the_round_trip() {
+        let mut rules = RuleVariant::Pisti.rules();
+        rules.majority_bonus = 7;
+        let config = GameConfig { variant: RuleVariant::Custom(rules), ..Default::default() };
+        let state = finished_game(4, config);
+
+        let record = GameRecord::from_game(&state, vec!["A".into(), "B".into()], record_date()).unwrap();
+        let parsed = GameRecord::parse(&record.to_string()).unwrap();
+        assert_eq!(parsed.config.variant, RuleVariant::Custom(rules));
+        assert!(parsed.replay().is_ok());
+    }
+
+    #[test]
+    fn test_tampered_records_are_rejected() {
+        let state = finished_game(8, GameConfig::default());
+        let record = GameRecord::from_game(&state, vec!["A".into(), "B".into()], record_date()).unwrap();
+
+        // A different result
+        let mut wrong_result = record.clone();
+        wrong_result.result[0] += 1;
+        assert!(wrong_result.replay().unwrap_err().contains("Recorded result"));
+
+        // A capture annotation the play did not make
+        let mut wrong_capture = record.clone();
+        wrong_capture.plays[0].captured += 5;
+        assert!(wrong_capture.replay().unwrap_err().starts_with("Move 1"));
+
+        // Moves out of order
+        let mut swapped = record.clone();
+        swapped.plays.swap(0, 1);
+        assert!(swapped.replay().is_err());
+
+        // A truncated game
+        let mut truncated = record.clone();
+        truncated.plays.pop();
+        assert!(truncated.replay().unwrap_err().contains("before the game is over"));
+
+        // Move numbers must count up from 1
+        let text = record.to_string().replace("\n2. ", "\n7. ");
+        assert!(GameRecord::parse(&text).unwrap_err().contains("Expected move 2"));
+    }
+
+    #[test]
+    fn test_forfeited_game_replays() {
+        let mut state = KseriGameState::with_seed(6);
+        for _ in 0..5 {
+            let player = state.current_turn;
+            let card = state.player_hands[player.index()][0];
+            state.play_card(player, card).unwrap();
+        }
+        state.forfeit(PlayerId::PLAYER_TWO);
+
+        let record = GameRecord::from_game(&state, vec!["A".into(), "B".into()], record_date()).unwrap();
+        let text = record.to_string();
+        assert!(text.contains("[Forfeit \"P2\"]"));
+        let parsed = GameRecord::parse(&text).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.replay().unwrap().pop().unwrap().move_history, state.move_history);
+
+        // Without the tag the record stops short of the end
+        let unfinished = GameRecord { forfeited: None, ..record };
+        assert!(unfinished.replay().unwrap_err().contains("before the game is over"));
+    }
+
+    #[test]
+    fn test_unfinished_game_has_no_record() {
+        let state = KseriGameState::with_seed(1);
+        assert!(GameRecord::from_game(&state, vec![], record_date()).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+
+use crate::server::messages::ServerMessage;
+
+/// Messages kept per seat for replaying after a reconnect
+pub const RESUME_BUFFER: usize = 256;
+
+/// Numbers the messages for one seat and keeps the latest ones, so a seat that lost its
+/// connection can get what it missed instead of a fresh snapshot
+#[derive(Debug, Clone)]
+pub struct SeatOutbox {
+    /// Sequence number of the last message, 0 before the first
+    last: u64,
+    /// The most recent messages with their sequence numbers, oldest first
+    buffer: VecDeque<(u64, ServerMessage)>,
+    capacity: usize,
+}
+
+impl Default for SeatOutbox {
+    fn default() -> Self {
+        Self::new(RESUME_BUFFER)
+    }
+}
+
+impl SeatOutbox {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            last: 0,
+            buffer: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn last_seq(&self) -> u64 {
+        self.last
+    }
+
+    /// Number `message`, keep it and return it wrapped in `ServerMessage::Sequenced`
+    pub fn push(&mut self, message: ServerMessage) -> ServerMessage {
+        self.last += 1;
+        if self.buffer.len() == self.capacity {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back((self.last, message.clone()));
+        ServerMessage::Sequenced {
+            seq: self.last,
+            message: Box::new(message),
+        }
+    }
+
+    /// The messages after `last_seen`, or `None` when some of them were already dropped
+    pub fn since(&self, last_seen: u64) -> Option<Vec<ServerMessage>> {
+        if last_seen > self.last {
+            return None;
+        }
+        let first = self.buffer.front().map_or(self.last + 1, |(seq, _)| *seq);
+        if last_seen + 1 < first {
+            return None;
+        }
+
+        Some(self.buffer.iter()
+            .filter(|(seq, _)| *seq > last_seen)
+            .map(|(seq, message)| ServerMessage::Sequenced {
+                seq: *seq,
+                message: Box::new(message.clone()),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seqs(messages: &[ServerMessage]) -> Vec<u64> {
+        messages.iter()
+            .map(|msg| match msg {
+                ServerMessage::Sequenced { seq, .. } => *seq,
+                _ => panic!("not sequenced: {:?}", msg),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replay_until_trimmed() {
+        let mut outbox = SeatOutbox::new(3);
+        assert_eq!(outbox.since(0).unwrap().len(), 0);
+        for _ in 0..3 {
+            outbox.push(ServerMessage::Pong);
+        }
+        assert_eq!(seqs(&outbox.since(0).unwrap()), vec![1, 2, 3]);
+        assert_eq!(seqs(&outbox.since(2).unwrap()), vec![3]);
+        assert!(outbox.since(3).unwrap().is_empty());
+
+        // The fourth message pushes out the first
+        assert!(matches!(outbox.push(ServerMessage::Pong), ServerMessage::Sequenced { seq: 4, .. }));
+        assert!(outbox.since(0).is_none());
+        assert_eq!(seqs(&outbox.since(1).unwrap()), vec![2, 3, 4]);
+        // A client cannot have seen more than was sent
+        assert!(outbox.since(5).is_none());
+    }
+}
@@ -0,0 +1,290 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::card::{Card, Rank, Suit};
+
+/// Largest value allowed for any single entry of a custom `RuleSet`
+pub const MAX_RULE_POINTS: u32 = 1000;
+/// Highest score a match may be played to
+pub const MAX_TARGET_SCORE: u32 = 1000;
+
+/// Scoring table consulted by `KseriGameState`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RuleSet {
+    /// Points for each Ace
+    pub ace_points: u32,
+    /// Points for each Jack other than the Jack of Diamonds
+    pub jack_points: u32,
+    pub jack_of_diamonds_points: u32,
+    pub two_of_clubs_points: u32,
+    pub ten_of_diamonds_points: u32,
+    /// Bonus for capturing a single table card with a card of the same rank
+    pub kseri_bonus: u32,
+    /// Bonus for capturing a single Jack with a Jack; `None` makes it an ordinary sweep
+    pub jack_kseri_bonus: Option<u32>,
+    /// Bonus for the player holding the majority of captured cards
+    pub majority_bonus: u32,
+}
+
+impl RuleSet {
+    /// Point value of a single captured card under these rules
+    pub fn card_value(&self, card: &Card) -> u32 {
+        match (card.suit, card.rank) {
+            (Suit::Clubs, Rank::Two) => self.two_of_clubs_points,
+            (Suit::Diamonds, Rank::Ten) => self.ten_of_diamonds_points,
+            (Suit::Diamonds, Rank::Jack) => self.jack_of_diamonds_points,
+            (_, Rank::Jack) => self.jack_points,
+            (_, Rank::Ace) => self.ace_points,
+            _ => 0,
+        }
+    }
+    
+    /// Points the cards of a full deck are worth together; every deal hands all of them out
+    pub fn deck_points(&self) -> u32 {
+        Suit::all().iter()
+            .flat_map(|&suit| Rank::all().map(move |rank| Card::new(suit, rank)))
+            .fold(0, |total, card| total.saturating_add(self.card_value(&card)))
+    }
+    
+    /// Refuse point tables with values above `MAX_RULE_POINTS`, so scores stay meaningful
+    pub fn validate(&self) -> Result<(), String> {
+        let entries = [
+            ("ace_points", self.ace_points),
+            ("jack_points", self.jack_points),
+            ("jack_of_diamonds_points", self.jack_of_diamonds_points),
+            ("two_of_clubs_points", self.two_of_clubs_points),
+            ("ten_of_diamonds_points", self.ten_of_diamonds_points),
+            ("kseri_bonus", self.kseri_bonus),
+            ("jack_kseri_bonus", self.jack_kseri_bonus.unwrap_or(0)),
+            ("majority_bonus", self.majority_bonus),
+        ];
+        match entries.iter().find(|(_, points)| *points > MAX_RULE_POINTS) {
+            Some((name, points)) => Err(format!("{} of {} is above the limit of {}", name, points, MAX_RULE_POINTS)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Named rule variants players can pick when queueing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum RuleVariant {
+    /// The original Kseri table: 2♣ = 2, 10♦ = 3, J♦ and Aces = 1
+    #[default]
+    Classic,
+    /// Turkish Pişti: every Jack scores and a Jack-on-Jack pişti is worth 20
+    Pisti,
+    /// House rules: 2♣ and 10♦ values swapped and a larger majority bonus
+    HouseValues,
+    /// An explicit point table agreed between the players
+    Custom(RuleSet),
+}
+
+impl RuleVariant {
+    /// The built-in named variants
+    pub fn all() -> [RuleVariant; 3] {
+        [RuleVariant::Classic, RuleVariant::Pisti, RuleVariant::HouseValues]
+    }
+    
+    pub fn rules(&self) -> RuleSet {
+        match self {
+            RuleVariant::Classic => RuleSet {
+                ace_points: 1,
+                jack_points: 0,
+                jack_of_diamonds_points: 1,
+                two_of_clubs_points: 2,
+                ten_of_diamonds_points: 3,
+                kseri_bonus: 10,
+                jack_kseri_bonus: None,
+                majority_bonus: 3,
+            },
+            RuleVariant::Pisti => RuleSet {
+                ace_points: 1,
+                jack_points: 1,
+                jack_of_diamonds_points: 1,
+                two_of_clubs_points: 2,
+                ten_of_diamonds_points: 3,
+                kseri_bonus: 10,
+                jack_kseri_bonus: Some(20),
+                majority_bonus: 3,
+            },
+            RuleVariant::HouseValues => RuleSet {
+                ace_points: 1,
+                jack_points: 0,
+                jack_of_diamonds_points: 1,
+                two_of_clubs_points: 3,
+                ten_of_diamonds_points: 2,
+                kseri_bonus: 10,
+                jack_kseri_bonus: None,
+                majority_bonus: 5,
+            },
+            RuleVariant::Custom(rules) => *rules,
+        }
+    }
+}
+
+/// Seating arrangement for a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Two players, one seat each
+    #[default]
+    HeadsUp,
+    /// Four seats in two partnerships; partners sit opposite (seats 0 & 2 and 1 & 3)
+    Partnership,
+    /// Three players, each scoring for themselves
+    ThreePlayer,
+    /// Four players, each scoring for themselves
+    FourPlayer,
+}
+
+impl GameMode {
+    pub fn seat_count(&self) -> usize {
+        match self {
+            GameMode::HeadsUp => 2,
+            GameMode::Partnership => 4,
+            GameMode::ThreePlayer => 3,
+            GameMode::FourPlayer => 4,
+        }
+    }
+    
+    /// Number of scoring sides; every seat is its own side outside partnership play
+    pub fn team_count(&self) -> usize {
+        match self {
+            GameMode::Partnership => 2,
+            _ => self.seat_count(),
+        }
+    }
+    
+    pub fn team_of(&self, seat: usize) -> usize {
+        seat % self.team_count()
+    }
+    
+    pub fn all() -> [GameMode; 4] {
+        [GameMode::HeadsUp, GameMode::Partnership, GameMode::ThreePlayer, GameMode::FourPlayer]
+    }
+    
+    pub fn is_partnership(&self) -> bool {
+        matches!(self, GameMode::Partnership)
+    }
+}
+
+/// Everything players agree on before a game is dealt; matchmaking only pairs equal configs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub variant: RuleVariant,
+    pub mode: GameMode,
+    /// Play a match of several deals up to this score; `None` plays a single deal
+    #[serde(default)]
+    pub target_score: Option<u32>,
+}
+
+impl GameConfig {
+    /// Check a config sent by a client before a game is set up with it.
+    /// A match needs a reachable target: above 0, at most `MAX_TARGET_SCORE`, and cards
+    /// that score, so the totals grow with every deal.
+    pub fn validate(&self) -> Result<(), String> {
+        let rules = self.variant.rules();
+        rules.validate()?;
+        
+        match self.target_score {
+            Some(0) => Err("Target score must be above 0".to_string()),
+            Some(target) if target > MAX_TARGET_SCORE => {
+                Err(format!("Target score of {} is above the limit of {}", target, MAX_TARGET_SCORE))
+            }
+            Some(_) if rules.deck_points() == 0 => Err("A match needs cards that score points".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_classic_matches_card_values() {
+        let rules = RuleVariant::Classic.rules();
+        
+        for suit in Suit::all() {
+            for rank in Rank::all() {
+                let card = Card::new(suit, rank);
+                assert_eq!(rules.card_value(&card), card.kseri_value(), "{}", card);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_variant_point_tables() {
+        let two_of_clubs = Card::new(Suit::Clubs, Rank::Two);
+        let ten_of_diamonds = Card::new(Suit::Diamonds, Rank::Ten);
+        let jack_of_spades = Card::new(Suit::Spades, Rank::Jack);
+        
+        let pisti = RuleVariant::Pisti.rules();
+        assert_eq!(pisti.card_value(&jack_of_spades), 1);
+        assert_eq!(pisti.jack_kseri_bonus, Some(20));
+        
+        let house = RuleVariant::HouseValues.rules();
+        assert_eq!(house.card_value(&two_of_clubs), 3);
+        assert_eq!(house.card_value(&ten_of_diamonds), 2);
+        
+        // Every variant still hands out points for the full deck
+        for variant in RuleVariant::all() {
+            assert!(variant.rules().deck_points() > 0);
+        }
+        assert_eq!(RuleVariant::Classic.rules().deck_points(), 10);
+    }
+    
+    #[test]
+    fn test_custom_rules_are_bounded() {
+        for variant in RuleVariant::all() {
+            assert!(variant.rules().validate().is_ok());
+        }
+        
+        let greedy = RuleSet { majority_bonus: u32::MAX, ..RuleVariant::Classic.rules() };
+        let config = GameConfig { variant: RuleVariant::Custom(greedy), ..Default::default() };
+        assert!(config.validate().unwrap_err().contains("majority_bonus"));
+        let generous = RuleSet { jack_kseri_bonus: Some(MAX_RULE_POINTS), ..greedy };
+        assert!(RuleSet { majority_bonus: 0, ..generous }.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_match_target_must_be_reachable() {
+        let target = |target_score| GameConfig { target_score, ..Default::default() };
+        assert!(target(None).validate().is_ok());
+        assert!(target(Some(101)).validate().is_ok());
+        assert!(target(Some(MAX_TARGET_SCORE)).validate().is_ok());
+        assert!(target(Some(0)).validate().is_err());
+        assert!(target(Some(MAX_TARGET_SCORE + 1)).validate().unwrap_err().contains("limit"));
+        
+        // Bonuses alone may never add up, so a match needs scoring cards; a single deal does not
+        let no_cards = RuleSet {
+            ace_points: 0,
+            jack_points: 0,
+            jack_of_diamonds_points: 0,
+            two_of_clubs_points: 0,
+            ten_of_diamonds_points: 0,
+            ..RuleVariant::Classic.rules()
+        };
+        let config = GameConfig { variant: RuleVariant::Custom(no_cards), target_score: Some(101), ..Default::default() };
+        assert!(config.validate().unwrap_err().contains("score points"));
+        assert!(GameConfig { target_score: None, ..config }.validate().is_ok());
+    }
+    
+    #[test]
+    fn test_partnership_teams() {
+        let mode = GameMode::Partnership;
+        
+        assert_eq!(mode.seat_count(), 4);
+        assert_eq!(mode.team_of(0), mode.team_of(2));
+        assert_eq!(mode.team_of(1), mode.team_of(3));
+        assert_ne!(mode.team_of(0), mode.team_of(1));
+    }
+    
+    #[test]
+    fn test_free_for_all_seats_score_alone() {
+        for mode in [GameMode::ThreePlayer, GameMode::FourPlayer] {
+            assert_eq!(mode.team_count(), mode.seat_count());
+            for seat in 0..mode.seat_count() {
+                assert_eq!(mode.team_of(seat), seat);
+            }
+        }
+    }
+}
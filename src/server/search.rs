@@ -0,0 +1,374 @@
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::components::card::{Card, PlayerId, Rank};
+use crate::server::bot::Bot;
+use crate::server::game_engine::{KseriGameState, PlayerView};
+
+/// UCB exploration constant
+const EXPLORATION: f64 = 0.7;
+/// Point margin that counts as a certain win or loss when scoring a playout
+const REWARD_SCALE: f64 = 20.0;
+
+/// How long a search may run; it stops at whichever limit is reached first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchBudget {
+    pub iterations: u32,
+    pub time_limit: Option<Duration>,
+}
+
+/// Named strengths for the search bot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub fn all() -> [Difficulty; 4] {
+        [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Expert]
+    }
+
+    pub fn budget(&self) -> SearchBudget {
+        match self {
+            Difficulty::Easy => SearchBudget { iterations: 50, time_limit: None },
+            Difficulty::Medium => SearchBudget { iterations: 500, time_limit: None },
+            Difficulty::Hard => SearchBudget { iterations: 3_000, time_limit: Some(Duration::from_millis(500)) },
+            Difficulty::Expert => SearchBudget { iterations: 20_000, time_limit: Some(Duration::from_secs(2)) },
+        }
+    }
+}
+
+/// Single-observer information-set MCTS.
+///
+/// Every iteration deals the cards this seat has not seen at random, consistent with
+/// the hand sizes, face-down cards and deck it can count, then walks one shared tree.
+/// Children are keyed by seat and card, so a node is only considered in the
+/// determinizations where its card is actually held.
+pub struct SearchBot {
+    budget: SearchBudget,
+    rng: StdRng,
+}
+
+struct Node {
+    /// The play leading to this node; `None` at the root
+    play: Option<(PlayerId, Card)>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    visits: u32,
+    /// Iterations in which this node's card could have been played
+    availability: u32,
+    /// Summed playout rewards for the team that made `play`
+    reward: f64,
+}
+
+impl Node {
+    fn new(play: Option<(PlayerId, Card)>, parent: Option<usize>) -> Self {
+        Self {
+            play,
+            parent,
+            children: Vec::new(),
+            visits: 0,
+            availability: 0,
+            reward: 0.0,
+        }
+    }
+
+    fn ucb(&self) -> f64 {
+        if self.visits == 0 {
+            return f64::INFINITY;
+        }
+        let visits = self.visits as f64;
+        self.reward / visits + EXPLORATION * ((self.availability.max(1) as f64).ln() / visits).sqrt()
+    }
+}
+
+impl SearchBot {
+    pub fn new(budget: SearchBudget) -> Self {
+        Self::with_rng(budget, StdRng::from_entropy())
+    }
+
+    /// A bot whose searches are reproducible
+    pub fn with_seed(budget: SearchBudget, seed: u64) -> Self {
+        Self::with_rng(budget, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(budget: SearchBudget, rng: StdRng) -> Self {
+        Self { budget, rng }
+    }
+
+    /// A full game state consistent with `view`, with the unseen cards dealt at random
+    pub fn determinize<R: Rng + ?Sized>(view: &PlayerView, rng: &mut R) -> KseriGameState {
+        let mut unseen = view.unseen_cards();
+        unseen.shuffle(rng);
+
+        let player_hands = view.hand_counts.iter().enumerate()
+            .map(|(seat, &count)| {
+                if seat == view.seat.index() {
+                    view.hand.clone()
+                } else {
+                    unseen.drain(..count).collect()
+                }
+            })
+            .collect();
+
+        let mut table_cards: Vec<Card> = unseen.drain(..view.table_hidden).collect();
+        table_cards.extend(&view.table_cards);
+
+        // Face-down cards captured by someone else sit at the bottom of their pile
+        let mut player_scores = view.captured.clone();
+        let mut hidden_capture = None;
+        for (seat, &count) in view.captured_hidden.iter().enumerate().filter(|(_, count)| **count > 0) {
+            let mut pile: Vec<Card> = unseen.drain(..count).collect();
+            pile.append(&mut player_scores[seat]);
+            player_scores[seat] = pile;
+            hidden_capture = Some((PlayerId(seat as u8), count));
+        }
+
+        // Whatever is left is the deck
+        debug_assert_eq!(unseen.len(), view.deck_remaining);
+
+        KseriGameState {
+            deck: unseen,
+            player_hands,
+            table_cards,
+            table_hidden: view.table_hidden,
+            hidden_capture,
+            player_scores,
+            current_turn: view.current_turn,
+            last_capture_player: view.last_capture_player,
+            game_over: false,
+            kseri_count: view.kseri_count.clone(),
+            jack_kseri_count: view.jack_kseri_count.clone(),
+            config: view.config,
+            seed: 0,
+            opening_lead: view.current_turn,
+            move_history: Vec::new(),
+            forfeited: None,
+        }
+    }
+
+    /// Run the search and return the root's play counts, most visited first
+    pub fn search(&mut self, view: &PlayerView) -> Vec<(Card, u32)> {
+        let started = Instant::now();
+        let mode = view.config.mode;
+        let mut nodes = vec![Node::new(None, None)];
+
+        for _ in 0..self.budget.iterations {
+            if self.budget.time_limit.is_some_and(|limit| started.elapsed() >= limit) {
+                break;
+            }
+
+            let mut state = Self::determinize(view, &mut self.rng);
+            let mut node = 0;
+
+            // Selection and expansion, restricted to plays legal in this determinization
+            while !state.game_over {
+                let seat = state.current_turn;
+                let hand = state.player_hands[seat.index()].clone();
+
+                let compatible: Vec<usize> = nodes[node].children.iter().copied()
+                    .filter(|&child| matches!(nodes[child].play, Some((s, card)) if s == seat && hand.contains(&card)))
+                    .collect();
+                for &child in &compatible {
+                    nodes[child].availability += 1;
+                }
+
+                let untried: Vec<Card> = hand.iter().copied()
+                    .filter(|card| !compatible.iter().any(|&child| nodes[child].play == Some((seat, *card))))
+                    .collect();
+
+                if let Some(&card) = untried.choose(&mut self.rng) {
+                    let child = nodes.len();
+                    let mut expanded = Node::new(Some((seat, card)), Some(node));
+                    expanded.availability = 1;
+                    nodes.push(expanded);
+                    nodes[node].children.push(child);
+
+                    state.play_card(seat, card).expect("expanded a card from the hand");
+                    node = child;
+                    break;
+                }
+
+                let Some(&best) = compatible.iter().max_by(|&&a, &&b| nodes[a].ucb().total_cmp(&nodes[b].ucb())) else {
+                    break;
+                };
+                let (_, card) = nodes[best].play.expect("child nodes record their play");
+                state.play_card(seat, card).expect("selected a card from the hand");
+                node = best;
+            }
+
+            // Greedy playout to the end of the deal
+            while !state.game_over {
+                let seat = state.current_turn;
+                let Some(card) = Self::playout_card(&state, &mut self.rng) else {
+                    break;
+                };
+                state.play_card(seat, card).expect("played a card from the hand");
+            }
+
+            // Backpropagate, crediting each play to the team that made it
+            let rewards = Self::rewards(&state);
+            let mut current = Some(node);
+            while let Some(idx) = current {
+                let entry = &mut nodes[idx];
+                entry.visits += 1;
+                if let Some((seat, _)) = entry.play {
+                    entry.reward += rewards[mode.team_of(seat.index())];
+                }
+                current = entry.parent;
+            }
+        }
+
+        let mut counts: Vec<(Card, u32)> = nodes[0].children.iter()
+            .filter_map(|&child| nodes[child].play.map(|(_, card)| (card, nodes[child].visits)))
+            .collect();
+        counts.sort_by_key(|(_, visits)| std::cmp::Reverse(*visits));
+        counts
+    }
+
+    /// Cheap playout policy: take the pile with a matching card, then with a Jack,
+    /// otherwise discard anything but a Jack
+    fn playout_card<R: Rng + ?Sized>(state: &KseriGameState, rng: &mut R) -> Option<Card> {
+        let hand = &state.player_hands[state.current_turn.index()];
+        let top = state.table_cards.last();
+
+        if let Some(&card) = hand.iter().find(|c| top.is_some_and(|top| top.rank == c.rank)) {
+            return Some(card);
+        }
+        let jack = hand.iter().find(|c| c.rank == Rank::Jack);
+        if let (Some(&card), Some(_)) = (jack, top) {
+            return Some(card);
+        }
+
+        let discards: Vec<Card> = hand.iter().copied().filter(|c| c.rank != Rank::Jack).collect();
+        discards.choose(rng).or(hand.first()).copied()
+    }
+
+    /// Reward per team in [0, 1] from its margin over the best other team
+    fn rewards(state: &KseriGameState) -> Vec<f64> {
+        let scores = state.calculate_scores();
+
+        (0..scores.len())
+            .map(|team| {
+                let best_other = scores.iter().enumerate()
+                    .filter(|(other, _)| *other != team)
+                    .map(|(_, score)| *score)
+                    .max()
+                    .unwrap_or(0);
+                let margin = scores[team] as f64 - best_other as f64;
+                (0.5 + margin / (2.0 * REWARD_SCALE)).clamp(0.0, 1.0)
+            })
+            .collect()
+    }
+}
+
+impl Bot for SearchBot {
+    fn choose_card(&mut self, view: &PlayerView) -> Option<Card> {
+        if view.hand.len() <= 1 {
+            return view.hand.first().copied();
+        }
+
+        self.search(view).first().map(|(card, _)| *card)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::rules::{GameConfig, GameMode};
+
+    /// A fresh deal where only one card is on the table and seat 0 holds a card of the same rank
+    fn kseri_chance() -> (KseriGameState, Card) {
+        (0..).find_map(|seed| {
+            let mut state = KseriGameState::with_seed(seed);
+            let hidden: Vec<Card> = state.table_cards.drain(..state.table_hidden).collect();
+            state.deck.extend(hidden);
+            state.table_hidden = 0;
+
+            let top = state.table_cards[0];
+            let pos = state.deck.iter().position(|c| c.rank == top.rank && c.rank != Rank::Jack)?;
+            let pair = state.deck[pos];
+            std::mem::swap(&mut state.deck[pos], &mut state.player_hands[0][0]);
+            Some((state, pair))
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_determinization_is_consistent_with_view() {
+        let mut state = KseriGameState::with_seed(4);
+        for _ in 0..5 {
+            let seat = state.current_turn;
+            let card = state.player_hands[seat.index()][0];
+            state.play_card(seat, card).unwrap();
+        }
+
+        let view = state.player_view(state.current_turn);
+        let seen = view.seen_cards();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..20 {
+            let sample = SearchBot::determinize(&view, &mut rng);
+            assert!(sample.validate_state().is_ok());
+            assert_eq!(sample.player_hands[view.seat.index()], view.hand);
+            assert_eq!(sample.visible_table_cards(), &view.table_cards[..]);
+            assert_eq!(sample.deck.len(), view.deck_remaining);
+
+            // Cards this seat has seen never end up somewhere it cannot see
+            let other_seat = view.seat.next(2).index();
+            assert_eq!(sample.player_hands[other_seat].len(), view.hand_counts[other_seat]);
+            assert!(sample.player_hands[other_seat].iter().all(|c| !seen.contains(c)));
+            assert!(sample.deck.iter().all(|c| !seen.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_search_takes_kseri() {
+        let (state, pair) = kseri_chance();
+        let mut bot = SearchBot::with_seed(Difficulty::Medium.budget(), 7);
+
+        assert_eq!(bot.choose_card(&state.player_view(PlayerId::PLAYER_ONE)), Some(pair));
+    }
+
+    #[test]
+    fn test_search_respects_budget() {
+        let state = KseriGameState::with_seed(2);
+        let view = state.player_view(PlayerId::PLAYER_ONE);
+
+        let mut bot = SearchBot::with_seed(SearchBudget { iterations: 40, time_limit: None }, 3);
+        let visits: u32 = bot.search(&view).iter().map(|(_, visits)| visits).sum();
+        assert_eq!(visits, 40);
+
+        let mut bot = SearchBot::with_seed(SearchBudget { iterations: u32::MAX, time_limit: Some(Duration::from_millis(50)) }, 3);
+        let started = Instant::now();
+        bot.search(&view);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_difficulty_budgets_grow() {
+        let budgets: Vec<u32> = Difficulty::all().iter().map(|d| d.budget().iterations).collect();
+        assert!(budgets.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_search_bots_play_out_partnership() {
+        let config = GameConfig { mode: GameMode::Partnership, ..Default::default() };
+        let mut state = KseriGameState::with_config(5, config);
+        let mut bot = SearchBot::with_seed(Difficulty::Easy.budget(), 11);
+
+        while !state.game_over {
+            let seat = state.current_turn;
+            let card = bot.choose_card(&state.player_view(seat)).unwrap();
+            state.play_card(seat, card).unwrap();
+        }
+        assert!(state.validate_state().is_ok());
+    }
+}
@@ -1,121 +1,320 @@
 use std::collections::VecDeque;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::time::{Duration, Instant};
 use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use rand::Rng;
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::components::card::{Card, PlayerId};
-use crate::server::messages::{ServerMessage, GameEvent, GameEventType};
+use crate::server::bot::BotKind;
+use crate::server::commands::{CommandLog, CommandOutcome};
+use crate::server::chat::{clean_chat, ChatState, Emote};
+use crate::server::clock::{lowest_card, AutoPlay, ClockCheck, ClockConfig, TurnClocks};
+use crate::server::event_log::GameLog;
+use crate::server::messages::{ServerMessage, GameEventType};
 use crate::server::game_engine::KseriGameState;
+use crate::server::match_play::{forfeit_winner, Match, MatchStatus, SeriesTally};
+use crate::server::persistence::{SessionSnapshot, SessionStore};
+use crate::server::accounts::{AccountStore, Login};
+use crate::server::protocol::Capability;
+use crate::server::resume::SeatOutbox;
+use crate::server::ratings::{LeaderboardEntry, RatingStore, INITIAL_RATING};
+use crate::server::record::{record_date, GameRecord};
+use crate::server::rules::{GameConfig, GameMode};
 
 const SESSION_TIMEOUT: Duration = Duration::from_secs(1800); // 30 minutes
 const RECONNECT_TIMEOUT: Duration = Duration::from_secs(30);
 const WAITING_TIMEOUT: Duration = Duration::from_secs(300); // 5 minutes
+const ROOM_TIMEOUT: Duration = Duration::from_secs(600); // 10 minutes
+/// Invite code characters, without the easily confused 0/O and 1/I
+const ROOM_CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const ROOM_CODE_LEN: usize = 6;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
     pub connection_id: Uuid,
     pub name: String,
     pub player_id: PlayerId,
     pub session_token: String,
+    /// Bots never connect, so they are skipped wherever messages are sent
+    #[serde(skip)]
     pub connected: bool,
+    #[serde(skip)]
     pub disconnected_at: Option<Instant>,
+    /// Set when the seat is played by the server
+    pub bot: Option<BotKind>,
+    /// Rated identity the player sent with `Identify`
+    #[serde(default)]
+    pub identity: Option<String>,
 }
 
+/// Shared by every task working on the session; the map of sessions hands out clones
+/// of the `Arc`, so no map lock is held while a task awaits
 pub struct GameSession {
     pub id: Uuid,
-    pub players: [Option<Player>; 2],
+    /// One slot per seat, indexed by `PlayerId`; only locked briefly, never across an await
+    seats: Mutex<Vec<Option<Player>>>,
+    /// The deal being played
     pub game_state: RwLock<KseriGameState>,
+    /// Running match when the players asked for a target score
+    pub match_play: RwLock<Option<Match>>,
     pub created_at: Instant,
     pub last_activity: RwLock<Instant>,
-    pub event_history: RwLock<VecDeque<GameEvent>>,
+    /// Every event of the session, never trimmed
+    pub event_history: RwLock<GameLog>,
+    /// Turn and game clocks, once the game has started with time limits
+    pub clocks: RwLock<Option<TurnClocks>>,
+    /// Connections watching the game; they never hold a seat or see a hand
+    watchers: Mutex<Vec<Uuid>>,
+    /// Results of the games played at this table, across rematches
+    pub tally: RwLock<SeriesTally>,
+    /// Seats that asked for or accepted a rematch of the finished game
+    pub rematch_votes: RwLock<Vec<PlayerId>>,
+    /// Chat rate limits and mutes
+    pub chat: RwLock<ChatState>,
+    /// Numbered messages per seat, for resuming after a reconnect
+    pub outboxes: RwLock<Vec<SeatOutbox>>,
+    /// Recent command outcomes per seat, so retries are not applied twice
+    pub commands: RwLock<Vec<CommandLog>>,
 }
 
 impl GameSession {
     pub fn new() -> Self {
+        Self::with_seed(rand::random(), GameConfig::default())
+    }
+    
+    /// Create a session whose game is dealt from `seed`, so it can be re-created later
+    pub fn with_seed(seed: u64, config: GameConfig) -> Self {
+        let match_play = config.target_score.map(|target| Match::new(config, target));
+        let game_state = match &match_play {
+            Some(current_match) => current_match.start_deal(seed),
+            None => KseriGameState::with_config(seed, config),
+        };
+        
+        let seats = config.mode.seat_count();
+        
         Self {
             id: Uuid::new_v4(),
-            players: [None, None],
-            game_state: RwLock::new(KseriGameState::new()),
+            seats: Mutex::new(vec![None; seats]),
+            game_state: RwLock::new(game_state),
+            match_play: RwLock::new(match_play),
             created_at: Instant::now(),
             last_activity: RwLock::new(Instant::now()),
-            event_history: RwLock::new(VecDeque::with_capacity(50)),
+            event_history: RwLock::new(GameLog::new()),
+            clocks: RwLock::new(None),
+            watchers: Mutex::new(Vec::new()),
+            tally: RwLock::new(SeriesTally::new(config.mode.team_count())),
+            rematch_votes: RwLock::new(Vec::new()),
+            chat: RwLock::new(ChatState::new(seats)),
+            outboxes: RwLock::new(vec![SeatOutbox::default(); seats]),
+            commands: RwLock::new(vec![CommandLog::default(); seats]),
         }
     }
     
-    pub fn add_player(&mut self, connection_id: Uuid, name: String) -> Result<(PlayerId, String), &'static str> {
-        // Find empty slot
-        let slot = if self.players[0].is_none() {
-            0
-        } else if self.players[1].is_none() {
-            1
-        } else {
-            return Err("Game session is full");
+    /// Bring a session back from disk. Nobody is connected yet, so every player
+    /// gets a fresh reconnect window starting now.
+    pub fn from_snapshot(snapshot: SessionSnapshot) -> Self {
+        let now = Instant::now();
+        let seats = snapshot.players.len();
+        let mut players = snapshot.players;
+        for player in players.iter_mut().flatten() {
+            player.connected = false;
+            player.disconnected_at = player.bot.is_none().then_some(now);
+        }
+        
+        Self {
+            id: snapshot.id,
+            seats: Mutex::new(players),
+            game_state: RwLock::new(snapshot.game_state),
+            match_play: RwLock::new(snapshot.match_play),
+            created_at: now.checked_sub(Duration::from_secs(snapshot.age_secs)).unwrap_or(now),
+            last_activity: RwLock::new(now),
+            event_history: RwLock::new(snapshot.events),
+            clocks: RwLock::new(None),
+            watchers: Mutex::new(Vec::new()),
+            tally: RwLock::new(snapshot.tally),
+            rematch_votes: RwLock::new(Vec::new()),
+            chat: RwLock::new(ChatState::new(seats)),
+            outboxes: RwLock::new(vec![SeatOutbox::default(); seats]),
+            commands: RwLock::new(vec![CommandLog::default(); seats]),
+        }
+    }
+    
+    pub async fn snapshot(&self) -> SessionSnapshot {
+        SessionSnapshot {
+            id: self.id,
+            players: self.players(),
+            game_state: self.game_state.read().await.clone(),
+            match_play: self.match_play.read().await.clone(),
+            age_secs: self.created_at.elapsed().as_secs(),
+            events: self.event_history.read().await.clone(),
+            tally: self.tally.read().await.clone(),
+        }
+    }
+    
+    /// Replace the finished game with a fresh one, the next seat leading this time.
+    /// A match starts over from zero with the next seat dealing first.
+    pub async fn start_rematch(&self, seed: u64) {
+        let mut game_state = self.game_state.write().await;
+        let mut match_play = self.match_play.write().await;
+        let seats = game_state.seat_count();
+        
+        match match_play.as_mut() {
+            Some(current_match) => {
+                let mut next_match = Match::new(current_match.config, current_match.target_score);
+                if let Some(first_deal) = current_match.deals.first() {
+                    next_match.dealer = first_deal.dealer.next(seats);
+                }
+                *game_state = next_match.start_deal(seed);
+                *current_match = next_match;
+            }
+            None => {
+                let lead = game_state.opening_lead.next(seats);
+                *game_state = KseriGameState::with_opening_lead(seed, game_state.config, lead);
+            }
+        }
+    }
+    
+    /// The seats, indexed by `PlayerId`, locked for a quick look or change
+    pub fn seats(&self) -> MutexGuard<'_, Vec<Option<Player>>> {
+        self.seats.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    
+    /// A copy of every seat, indexed by `PlayerId`
+    pub fn players(&self) -> Vec<Option<Player>> {
+        self.seats().clone()
+    }
+    
+    pub fn spectators(&self) -> Vec<Uuid> {
+        self.watchers().clone()
+    }
+    
+    fn watchers(&self) -> MutexGuard<'_, Vec<Uuid>> {
+        self.watchers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    
+    pub fn add_spectator(&self, connection_id: Uuid) -> usize {
+        let mut watchers = self.watchers();
+        watchers.push(connection_id);
+        watchers.len()
+    }
+    
+    /// Stop counting `connection_id` as a spectator; false when it was not watching
+    pub fn remove_spectator(&self, connection_id: Uuid) -> bool {
+        let mut watchers = self.watchers();
+        let Some(idx) = watchers.iter().position(|conn| *conn == connection_id) else {
+            return false;
         };
+        watchers.remove(idx);
+        true
+    }
+    
+    pub fn add_player(&self, connection_id: Uuid, name: String) -> Result<(PlayerId, String), &'static str> {
+        let mut seats = self.seats();
         
-        let player_id = if slot == 0 { PlayerId::PLAYER_ONE } else { PlayerId::PLAYER_TWO };
+        // Find empty slot
+        let slot = seats.iter()
+            .position(|p| p.is_none())
+            .ok_or("Game session is full")?;
+        
+        let player_id = PlayerId(slot as u8);
         let session_token = format!("{}-{}", self.id, Uuid::new_v4());
         
-        self.players[slot] = Some(Player {
+        seats[slot] = Some(Player {
             connection_id,
             name: name.clone(),
             player_id,
             session_token: session_token.clone(),
             connected: true,
             disconnected_at: None,
+            bot: None,
+            identity: None,
         });
         
         Ok((player_id, session_token))
     }
     
+    /// Seat a bot in every empty slot
+    pub fn fill_with_bots(&self, kind: BotKind) {
+        let session_id = self.id;
+        
+        for (slot, seat) in self.seats().iter_mut().enumerate() {
+            if seat.is_none() {
+                *seat = Some(Player {
+                    connection_id: Uuid::new_v4(),
+                    name: kind.name().to_string(),
+                    player_id: PlayerId(slot as u8),
+                    session_token: format!("{}-{}", session_id, Uuid::new_v4()),
+                    connected: false,
+                    disconnected_at: None,
+                    bot: Some(kind),
+                    identity: None,
+                });
+            }
+        }
+    }
+    
     pub fn is_ready(&self) -> bool {
-        self.players[0].is_some() && self.players[1].is_some()
+        self.seats().iter().all(|p| p.is_some())
+    }
+    
+    pub fn seat_count(&self) -> usize {
+        self.seats().len()
     }
     
-    pub fn get_player_by_connection(&self, connection_id: Uuid) -> Option<&Player> {
-        self.players.iter()
+    pub fn get_player_by_connection(&self, connection_id: Uuid) -> Option<Player> {
+        self.seats().iter()
             .filter_map(|p| p.as_ref())
             .find(|p| p.connection_id == connection_id)
+            .cloned()
     }
     
-    pub fn get_player_by_token(&self, token: &str) -> Option<&Player> {
-        self.players.iter()
+    pub fn get_player_by_token(&self, token: &str) -> Option<Player> {
+        self.seats().iter()
             .filter_map(|p| p.as_ref())
             .find(|p| p.session_token == token)
+            .cloned()
+    }
+    
+    /// Names indexed by seat, empty for open seats
+    pub fn player_names(&self) -> Vec<String> {
+        self.seats().iter()
+            .map(|p| p.as_ref().map(|p| p.name.clone()).unwrap_or_default())
+            .collect()
     }
     
-    pub fn get_opponent(&self, player_id: PlayerId) -> Option<&Player> {
-        let opponent_idx = if player_id == PlayerId::PLAYER_ONE { 1 } else { 0 };
-        self.players[opponent_idx].as_ref()
+    /// Everyone seated at the table except `player_id`
+    pub fn other_players(&self, player_id: PlayerId) -> Vec<Player> {
+        self.seats().iter()
+            .filter_map(|p| p.as_ref())
+            .filter(|p| p.player_id != player_id)
+            .cloned()
+            .collect()
     }
     
     pub async fn add_event(&self, event_type: GameEventType) {
-        let event = GameEvent {
-            session_id: self.id,
-            event_type,
-            timestamp: Instant::now(),
-        };
-        
-        let mut history = self.event_history.write().await;
-        history.push_back(event);
-        if history.len() > 50 {
-            history.pop_front();
-        }
-        
+        self.event_history.write().await.append(self.id, event_type);
         *self.last_activity.write().await = Instant::now();
     }
     
     pub fn is_expired(&self) -> bool {
+        let seats = self.seats();
+        
         // Check if all players are disconnected
-        let all_disconnected = self.players.iter()
+        let all_disconnected = seats.iter()
             .filter_map(|p| p.as_ref())
+            .filter(|p| p.bot.is_none())
             .all(|p| !p.connected);
             
         if all_disconnected {
             // Check disconnection timeout
-            let max_disconnect_time = self.players.iter()
+            let max_disconnect_time = seats.iter()
                 .filter_map(|p| p.as_ref())
                 .filter_map(|p| p.disconnected_at)
                 .max();
@@ -134,12 +333,43 @@ pub struct WaitingPlayer {
     pub connection_id: Uuid,
     pub name: String,
     pub joined_at: Instant,
+    /// Open session this player is seated in, waiting for the remaining seats
+    pub session_id: Uuid,
+    pub config: GameConfig,
+}
+
+/// Private table waiting for the players its invite code was shared with
+pub struct Room {
+    pub session_id: Uuid,
+    pub created_at: Instant,
+}
+
+/// Random invite code such as `K7QM2X`
+fn room_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..ROOM_CODE_LEN)
+        .map(|_| ROOM_CODE_CHARS[rng.gen_range(0..ROOM_CODE_CHARS.len())] as char)
+        .collect()
 }
 
 pub struct SessionManager {
-    pub sessions: Arc<DashMap<Uuid, GameSession>>,
+    pub sessions: Arc<DashMap<Uuid, Arc<GameSession>>>,
     waiting_queue: Arc<RwLock<VecDeque<WaitingPlayer>>>,
     connection_to_session: Arc<DashMap<Uuid, Uuid>>,
+    /// Private tables that are not full yet, by invite code
+    rooms: Arc<DashMap<String, Room>>,
+    /// Seat bots at tables that have waited this long for players
+    bot_fallback: Option<Duration>,
+    /// Directory finished games are saved to as text records
+    record_dir: Option<PathBuf>,
+    /// Snapshots of running games, so they survive a restart
+    store: Option<SessionStore>,
+    /// Time limits for human seats; untimed when `None`
+    clock: Option<ClockConfig>,
+    /// Player ratings, updated after every game between rated players
+    ratings: RatingStore,
+    /// Registered players, who keep one identity across games and devices
+    accounts: AccountStore,
 }
 
 impl SessionManager {
@@ -148,161 +378,498 @@ impl SessionManager {
             sessions: Arc::new(DashMap::new()),
             waiting_queue: Arc::new(RwLock::new(VecDeque::new())),
             connection_to_session: Arc::new(DashMap::new()),
+            rooms: Arc::new(DashMap::new()),
+            bot_fallback: None,
+            record_dir: None,
+            store: None,
+            clock: None,
+            ratings: RatingStore::in_memory(),
+            accounts: AccountStore::in_memory(),
+        }
+    }
+    
+    /// The session with this id. The map entry is released before returning,
+    /// so the session can be held across awaits.
+    pub fn session(&self, session_id: &Uuid) -> Option<Arc<GameSession>> {
+        self.sessions.get(session_id).map(|entry| Arc::clone(entry.value()))
+    }
+    
+    /// Fill waiting tables with bots once they have waited `wait`; see `fill_waiting_with_bots`
+    pub fn with_bot_fallback(mut self, wait: Duration) -> Self {
+        self.bot_fallback = Some(wait);
+        self
+    }
+    
+    /// Save a `GameRecord` of every finished deal into `dir`
+    pub fn with_record_dir(mut self, dir: PathBuf) -> Self {
+        self.record_dir = Some(dir);
+        self
+    }
+    
+    /// Snapshot every started game into `store` after each move
+    pub fn with_store(mut self, store: SessionStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+    
+    /// Time every human move, see `enforce_turn_clocks`
+    pub fn with_turn_clock(mut self, config: ClockConfig) -> Self {
+        self.clock = Some(config);
+        self
+    }
+    
+    /// Keep ratings in `ratings` instead of in memory
+    pub fn with_ratings(mut self, ratings: RatingStore) -> Self {
+        self.ratings = ratings;
+        self
+    }
+    
+    /// Keep player accounts in `accounts` instead of in memory
+    pub fn with_accounts(mut self, accounts: AccountStore) -> Self {
+        self.accounts = accounts;
+        self
+    }
+    
+    /// Load the games saved in the store, returning how many were restored.
+    /// Players get them back with `Reconnect` and their old session token.
+    pub fn restore_sessions(&self) -> std::io::Result<usize> {
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+        
+        let snapshots = store.load_all()?;
+        let restored = snapshots.len();
+        for snapshot in snapshots {
+            let mut session = GameSession::from_snapshot(snapshot);
+            // Clocks are not saved; the turn in progress starts over
+            session.clocks = RwLock::new(self.new_clocks(&session));
+            info!("Restored session {}", session.id);
+            self.sessions.insert(session.id, Arc::new(session));
+        }
+        
+        Ok(restored)
+    }
+    
+    /// Write a snapshot of the session, once its game has started
+    async fn persist(&self, session: &GameSession) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        if !session.is_ready() {
+            return;
+        }
+        
+        if let Err(e) = store.save(&session.snapshot().await).await {
+            warn!("Failed to save session {}: {}", session.id, e);
+        }
+    }
+    
+    /// Snapshot every session, for a clean shutdown
+    pub async fn persist_all(&self) -> usize {
+        let session_ids: Vec<Uuid> = self.sessions.iter().map(|entry| *entry.key()).collect();
+        let mut saved = 0;
+        
+        for session_id in session_ids {
+            if let Some(session) = self.session(&session_id) {
+                if session.is_ready() {
+                    self.persist(&session).await;
+                    saved += 1;
+                }
+            }
+        }
+        
+        saved
+    }
+    
+    /// Start a game right away with bots in every other seat
+    pub async fn create_bot_game(
+        &self,
+        connection_id: Uuid,
+        player_name: String,
+        config: GameConfig,
+        kind: BotKind,
+    ) -> Result<(Uuid, PlayerId, String), Box<dyn std::error::Error + Send + Sync>> {
+        if self.connection_to_session.contains_key(&connection_id) {
+            return Err("Already in a game session".into());
+        }
+        config.validate()?;
+        
+        let session = GameSession::with_seed(rand::random(), config);
+        let session_id = session.id;
+        let (player_id, token) = session.add_player(connection_id, player_name)?;
+        session.fill_with_bots(kind);
+        
+        self.sessions.insert(session_id, Arc::new(session));
+        self.connection_to_session.insert(connection_id, session_id);
+        
+        info!("Player {} started a bot game in session {} ({:?})", connection_id, session_id, config);
+        
+        Ok((session_id, player_id, token))
+    }
+    
+    /// Seat bots at every table that has waited longer than the fallback, and start those games
+    pub async fn fill_waiting_with_bots(&self) -> Vec<(Uuid, ServerMessage)> {
+        let Some(wait) = self.bot_fallback else {
+            return Vec::new();
+        };
+        
+        let mut filled = Vec::new();
+        {
+            let mut queue = self.waiting_queue.write().await;
+            queue.retain(|waiting| {
+                if waiting.joined_at.elapsed() < wait {
+                    return true;
+                }
+                if let Some(session) = self.session(&waiting.session_id) {
+                    session.fill_with_bots(BotKind::default());
+                    filled.push(waiting.session_id);
+                }
+                false
+            });
+        }
+        
+        let mut messages = Vec::new();
+        for session_id in filled {
+            info!("Session {} waited too long, filled with bots", session_id);
+            messages.extend(self.check_game_ready(session_id).await.unwrap_or_default());
+        }
+        
+        messages
+    }
+    
+    /// Open a private table reachable only through the returned invite code
+    pub async fn create_room(
+        &self,
+        connection_id: Uuid,
+        player_name: String,
+        config: GameConfig,
+    ) -> Result<(Uuid, PlayerId, String, String), Box<dyn std::error::Error + Send + Sync>> {
+        if self.connection_to_session.contains_key(&connection_id) {
+            return Err("Already in a game session".into());
+        }
+        config.validate()?;
+        
+        let session = GameSession::with_seed(rand::random(), config);
+        let session_id = session.id;
+        let (player_id, token) = session.add_player(connection_id, player_name)?;
+        self.sessions.insert(session_id, Arc::new(session));
+        self.connection_to_session.insert(connection_id, session_id);
+        
+        let code = loop {
+            let code = room_code();
+            if let Entry::Vacant(entry) = self.rooms.entry(code.clone()) {
+                entry.insert(Room { session_id, created_at: Instant::now() });
+                break code;
+            }
+        };
+        
+        info!("Player {} opened room {} in session {} ({:?})", connection_id, code, session_id, config);
+        
+        Ok((session_id, player_id, token, code))
+    }
+    
+    /// Take a seat at the private table with invite code `code`; codes ignore case
+    pub async fn join_room(
+        &self,
+        connection_id: Uuid,
+        player_name: String,
+        code: &str,
+    ) -> Result<(Uuid, PlayerId, String), Box<dyn std::error::Error + Send + Sync>> {
+        if self.connection_to_session.contains_key(&connection_id) {
+            return Err("Already in a game session".into());
+        }
+        
+        let code = code.trim().to_ascii_uppercase();
+        let session_id = self.rooms.get(&code)
+            .map(|room| room.session_id)
+            .ok_or("No room with that code")?;
+        
+        let session = self.session(&session_id)
+            .ok_or("Room has closed")?;
+        let (player_id, token) = session.add_player(connection_id, player_name)?;
+        let full = session.is_ready();
+        drop(session);
+        
+        self.connection_to_session.insert(connection_id, session_id);
+        if full {
+            self.rooms.remove(&code);
+            info!("Room {} is full", code);
         }
+        
+        Ok((session_id, player_id, token))
     }
     
     pub async fn join_or_create_game(
         &self,
         connection_id: Uuid,
         player_name: String,
+        config: GameConfig,
     ) -> Result<(Uuid, PlayerId, String), Box<dyn std::error::Error + Send + Sync>> {
         // Check if player is already in a session
         if let Some(_session_id) = self.connection_to_session.get(&connection_id) {
             return Err("Already in a game session".into());
         }
+        config.validate()?;
         
         let mut queue = self.waiting_queue.write().await;
         
-        // Check if there's a waiting player
-        if let Some(waiting) = queue.pop_front() {
-            // Create new game session
-            let mut session = GameSession::new();
-            let session_id = session.id;
+        // Drop entries whose session has already been cleaned up
+        queue.retain(|waiting| self.sessions.contains_key(&waiting.session_id));
+        
+        // Join the longest-waiting open session with the same rules and seating
+        if let Some(pos) = queue.iter().position(|waiting| waiting.config == config) {
+            let session_id = queue[pos].session_id;
             
-            // Add both players
-            let (_p1_id, _p1_token) = session.add_player(waiting.connection_id, waiting.name)?;
-            let (p2_id, p2_token) = session.add_player(connection_id, player_name.clone())?;
+            // Take the next free seat in the waiting session
+            let session = self.session(&session_id)
+                .ok_or("Waiting session not found")?;
+            let (player_id, token) = session.add_player(connection_id, player_name)?;
+            let full = session.is_ready();
+            drop(session);
             
-            // Store session
-            self.sessions.insert(session_id, session);
-            self.connection_to_session.insert(waiting.connection_id, session_id);
             self.connection_to_session.insert(connection_id, session_id);
             
-            info!("Created game session {} with two players", session_id);
+            if full {
+                queue.remove(pos);
+                info!("Session {} is full ({:?})", session_id, config);
+            } else {
+                info!("Player {} seated in session {}, waiting for more players", connection_id, session_id);
+            }
             
-            // Return info for the current player
-            Ok((session_id, p2_id, p2_token))
+            Ok((session_id, player_id, token))
         } else {
+            // Create the session now so the waiting player already holds a seat
+            let session = GameSession::with_seed(rand::random(), config);
+            let session_id = session.id;
+            let (player_id, token) = session.add_player(connection_id, player_name.clone())?;
+            
+            self.sessions.insert(session_id, Arc::new(session));
+            self.connection_to_session.insert(connection_id, session_id);
+            
             // Add to waiting queue
             queue.push_back(WaitingPlayer {
                 connection_id,
-                name: player_name.clone(),
+                name: player_name,
                 joined_at: Instant::now(),
+                session_id,
+                config,
             });
             
-            info!("Player {} added to waiting queue", connection_id);
-            
-            // Create placeholder session for waiting player
-            let mut session = GameSession::new();
-            let session_id = session.id;
-            let (player_id, token) = session.add_player(connection_id, player_name)?;
-            
-            self.sessions.insert(session_id, session);
-            self.connection_to_session.insert(connection_id, session_id);
+            info!("Player {} added to waiting queue ({:?})", connection_id, config);
             
             Ok((session_id, player_id, token))
         }
     }
     
     pub async fn check_game_ready(&self, session_id: Uuid) -> Option<Vec<(Uuid, ServerMessage)>> {
-        let session = self.sessions.get(&session_id)?;
+        let session = self.session(&session_id)?;
         
         if !session.is_ready() {
             return None;
         }
         
-        let mut messages = Vec::new();
         let game_state = session.game_state.read().await;
+        let match_status = session.match_play.read().await.as_ref().map(Match::status);
+        let mut messages = self.start_messages(&session, &game_state, match_status).await;
+        let deal_started = Self::deal_started(&game_state);
         
-        // Create start messages for both players
-        for (idx, player) in session.players.iter().enumerate() {
-            if let Some(p) = player {
-                let opponent = session.players[1 - idx].as_ref()?;
+        drop(game_state);
+        *session.clocks.write().await = self.new_clocks(&session);
+        for player in session.players().iter().flatten() {
+            session.add_event(GameEventType::PlayerJoined(player.player_id, player.name.clone())).await;
+        }
+        session.add_event(deal_started).await;
+        
+        // A bot may hold the opening lead
+        messages.extend(self.play_bot_turns(&session).await);
+        self.persist(&session).await;
+        
+        Some(messages)
+    }
+    
+    fn new_clocks(&self, session: &GameSession) -> Option<TurnClocks> {
+        self.clock.map(|config| TurnClocks::new(config, session.seat_count()))
+    }
+    
+    /// Log entry that, with the plays after it, rebuilds `game_state`
+    fn deal_started(game_state: &KseriGameState) -> GameEventType {
+        GameEventType::DealStarted {
+            seed: game_state.seed,
+            config: game_state.config,
+            opening_lead: game_state.opening_lead,
+        }
+    }
+    
+    /// `GameStarted` for every seat, sent when the table fills and at the start of each deal
+    async fn start_messages(&self, session: &GameSession, game_state: &KseriGameState, match_status: Option<MatchStatus>) -> Vec<(Uuid, ServerMessage)> {
+        let mut messages = Vec::new();
+        let player_ratings = self.seat_ratings(session).await;
+        let player_names: Vec<String> = session.players().iter()
+            .filter_map(|p| p.as_ref())
+            .map(|p| p.name.clone())
+            .collect();
+        
+        for (idx, player) in session.players().iter().enumerate() {
+            if let Some(p) = player.as_ref().filter(|p| p.bot.is_none()) {
+                // The opponent is whoever plays right after this seat
+                let opponent_name = session.players()[(idx + 1) % session.seat_count()].as_ref()
+                    .map(|opponent| opponent.name.clone())
+                    .unwrap_or_default();
                 
                 let msg = ServerMessage::GameStarted {
-                    opponent_name: opponent.name.clone(),
+                    opponent_name,
                     your_turn: p.player_id == game_state.current_turn,
                     initial_hand: game_state.player_hands[idx].clone(),
-                    table_cards: game_state.table_cards.clone(),
+                    table_cards: game_state.visible_table_cards().to_vec(),
+                    hidden_table_cards: game_state.table_hidden as u8,
+                    variant: game_state.config.variant,
+                    mode: game_state.mode(),
+                    player_names: player_names.clone(),
+                    match_status: match_status.clone(),
+                    player_ratings: player_ratings.clone(),
                 };
                 
                 messages.push((p.connection_id, msg));
             }
         }
         
-        drop(game_state);
-        session.add_event(GameEventType::PlayerJoined(PlayerId::PLAYER_TWO, session.players[1].as_ref()?.name.clone())).await;
+        let spectator_state = Self::spectator_state(session, game_state, match_status);
+        messages.extend(session.spectators().iter().map(|conn| (*conn, spectator_state.clone())));
         
-        Some(messages)
+        messages
+    }
+    
+    /// What spectators see of the table
+    fn spectator_state(session: &GameSession, game_state: &KseriGameState, match_status: Option<MatchStatus>) -> ServerMessage {
+        ServerMessage::SpectatorState {
+            player_names: session.player_names(),
+            current_turn: game_state.current_turn,
+            table_cards: game_state.visible_table_cards().to_vec(),
+            hidden_table_cards: game_state.table_hidden as u8,
+            hand_counts: game_state.player_hands.iter().map(|hand| hand.len() as u8).collect(),
+            deck_remaining: game_state.deck.len() as u8,
+            team_scores: game_state.calculate_scores(),
+            contributions: game_state.seat_points(),
+            variant: game_state.config.variant,
+            mode: game_state.mode(),
+            match_status,
+            spectators: session.spectators().len() as u32,
+        }
+    }
+    
+    /// Watch a game without a seat. Returns the table for the spectator and
+    /// the new spectator count for everyone playing.
+    pub async fn spectate(
+        &self,
+        connection_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<(ServerMessage, Vec<(Uuid, ServerMessage)>), Box<dyn std::error::Error + Send + Sync>> {
+        if self.connection_to_session.contains_key(&connection_id) {
+            return Err("Already in a game session".into());
+        }
+        
+        let session = self.session(&session_id)
+            .ok_or("Session not found")?;
+        session.add_spectator(connection_id);
+        self.connection_to_session.insert(connection_id, session_id);
+        
+        info!("Connection {} is watching session {}", connection_id, session_id);
+        
+        let game_state = session.game_state.read().await;
+        let match_status = session.match_play.read().await.as_ref().map(Match::status);
+        let state = Self::spectator_state(&session, &game_state, match_status);
+        
+        Ok((state, Self::spectators_changed(&session)))
+    }
+    
+    /// New spectator count for every player at the table
+    fn spectators_changed(session: &GameSession) -> Vec<(Uuid, ServerMessage)> {
+        let msg = ServerMessage::SpectatorsChanged {
+            count: session.spectators().len() as u32,
+        };
+        session.players().iter()
+            .filter_map(|p| p.as_ref())
+            .filter(|p| p.bot.is_none())
+            .map(|p| (p.connection_id, msg.clone()))
+            .collect()
     }
     
+    /// Take a seat back with its session token. The returned messages are already numbered.
+    /// When the client negotiated `resume` and sent `last_seq`, the last sequence number it
+    /// saw, the missed messages are replayed, or a `Resync` snapshot is sent when they are no
+    /// longer buffered; otherwise the client gets a `StateUpdate` as before.
     pub async fn reconnect_player(
         &self,
         connection_id: Uuid,
         session_token: String,
+        last_seq: Option<u64>,
+        resume: bool,
     ) -> Result<(Uuid, PlayerId, Vec<ServerMessage>), Box<dyn std::error::Error + Send + Sync>> {
-        // Find session by token and get the session ID
-        let session_id = {
-            let session_entry = self.sessions.iter()
-                .find(|entry| {
-                    entry.value().players.iter()
-                        .filter_map(|p| p.as_ref())
-                        .any(|p| p.session_token == session_token)
-                })
-                .ok_or("Invalid session token")?;
-            *session_entry.key()
-        };
-        
-        // Get mutable session reference
-        let mut session_entry = self.sessions.get_mut(&session_id)
-            .ok_or("Session not found")?;
-        let session = session_entry.value_mut();
+        // Find session by token
+        let session = self.sessions.iter()
+            .find(|entry| entry.value().get_player_by_token(&session_token).is_some())
+            .map(|entry| Arc::clone(entry.value()))
+            .ok_or("Invalid session token")?;
+        let session_id = session.id;
         
         // Find and update player
-        let player_idx = session.players.iter()
-            .position(|p| p.as_ref().map(|p| p.session_token == session_token).unwrap_or(false))
-            .ok_or("Player not found in session")?;
+        let (player_id, old_connection) = {
+            let mut seats = session.seats();
+            let player = seats.iter_mut()
+                .filter_map(|p| p.as_mut())
+                .find(|p| p.session_token == session_token)
+                .ok_or("Player not found in session")?;
             
-        let player = session.players[player_idx].as_mut()
-            .ok_or("Player slot is empty")?;
-            
-        // Check reconnection timeout
-        if let Some(disconnect_time) = player.disconnected_at {
-            if disconnect_time.elapsed() > RECONNECT_TIMEOUT {
-                return Err("Reconnection timeout expired".into());
+            // Check reconnection timeout
+            if let Some(disconnect_time) = player.disconnected_at {
+                if disconnect_time.elapsed() > RECONNECT_TIMEOUT {
+                    return Err("Reconnection timeout expired".into());
+                }
             }
-        }
-        
-        // Update player connection
-        player.connection_id = connection_id;
-        player.connected = true;
-        player.disconnected_at = None;
-        
-        let player_id = player.player_id;
-        
-        // Drop the mutable reference to session
-        drop(session_entry);
+            
+            // Update player connection
+            let old_connection = std::mem::replace(&mut player.connection_id, connection_id);
+            player.connected = true;
+            player.disconnected_at = None;
+            (player.player_id, old_connection)
+        };
         
         // Update mappings
+        self.connection_to_session.remove(&old_connection);
         self.connection_to_session.insert(connection_id, session_id);
         
-        // Prepare reconnection messages
-        let mut messages = vec![
-            ServerMessage::Connected {
-                player_id,
-                session_token: session_token.clone(),
-            }
-        ];
-        
-        // Get session again for reading
-        let session = self.sessions.get(&session_id)
-            .ok_or("Session not found")?;
+        // Number the confirmation like any other message for the seat, so a replay
+        // ends with it. The sequence number is read first, so a snapshot is at least that recent.
+        let last_seq = last_seq.filter(|_| resume);
+        let mut outboxes = session.outboxes.write().await;
+        let outbox = &mut outboxes[player_id.index()];
+        let connected = outbox.push(ServerMessage::Connected {
+            player_id,
+            session_token: session_token.clone(),
+        });
+        let seq = outbox.last_seq();
+        let replay = last_seq.and_then(|seen| outbox.since(seen));
+        drop(outboxes);
         
-        // Send current game state
-        let game_state = session.game_state.read().await;
-        let state_msg = self.create_state_message(&game_state, player_id, &session);
-        messages.push(state_msg);
-        drop(game_state);
+        // Replay what the client missed, or send the current game state
+        let messages = match replay {
+            Some(missed) => missed,
+            None => {
+                let game_state = session.game_state.read().await;
+                let clocks = session.clocks.read().await;
+                let state_msg = self.create_state_message(&game_state, player_id, &session, clocks.as_ref());
+                let state_msg = if last_seq.is_some() {
+                    ServerMessage::Resync {
+                        seq,
+                        hand: game_state.player_hands[player_id.index()].clone(),
+                        state: Box::new(state_msg),
+                    }
+                } else {
+                    session.outboxes.write().await[player_id.index()].push(state_msg)
+                };
+                vec![connected, state_msg]
+            }
+        };
         
         // Add reconnection event
         session.add_event(GameEventType::PlayerReconnected(player_id)).await;
@@ -316,7 +883,7 @@ impl SessionManager {
         connection_id: Uuid,
         card: Card,
     ) -> Result<Vec<(Uuid, ServerMessage)>, Box<dyn std::error::Error + Send + Sync>> {
-        let session = self.sessions.get(&session_id)
+        let session = self.session(&session_id)
             .ok_or("Session not found")?;
             
         // Verify player
@@ -325,34 +892,109 @@ impl SessionManager {
             
         let player_id = player.player_id;
         
+        let mut messages = self.apply_play(&session, player_id, card).await?;
+        if let Some(clocks) = session.clocks.write().await.as_mut() {
+            clocks.reset_timeouts(player_id);
+        }
+        messages.extend(self.play_bot_turns(&session).await);
+        self.persist(&session).await;
+        
+        Ok(messages)
+    }
+    
+    /// Play for every bot whose turn it is, until a human is to move or the game is over
+    async fn play_bot_turns(&self, session: &GameSession) -> Vec<(Uuid, ServerMessage)> {
+        let mut messages = Vec::new();
+        
+        loop {
+            let (seat, kind, view) = {
+                let game_state = session.game_state.read().await;
+                if game_state.game_over {
+                    break;
+                }
+                
+                let seat = game_state.current_turn;
+                let Some(kind) = session.players()[seat.index()].as_ref().and_then(|p| p.bot) else {
+                    break;
+                };
+                (seat, kind, game_state.player_view(seat))
+            };
+            
+            // Searching bots can think for a while, so keep them off the async workers
+            let choice = tokio::task::spawn_blocking(move || kind.create().choose_card(&view)).await;
+            let Ok(Some(card)) = choice else {
+                warn!("Bot in seat {:?} of session {} did not choose a card", seat, session.id);
+                break;
+            };
+            
+            match self.apply_play(session, seat, card).await {
+                Ok(played) => messages.extend(played),
+                Err(e) => {
+                    warn!("Bot in seat {:?} of session {} made an invalid play: {}", seat, session.id, e);
+                    break;
+                }
+            }
+        }
+        
+        messages
+    }
+    
+    /// Play `card` for the seat `player_id` and build the messages for everyone at the table
+    async fn apply_play(
+        &self,
+        session: &GameSession,
+        player_id: PlayerId,
+        card: Card,
+    ) -> Result<Vec<(Uuid, ServerMessage)>, Box<dyn std::error::Error + Send + Sync>> {
         // Play the card
         let mut game_state = session.game_state.write().await;
         let result = game_state.play_card(player_id, card)?;
+        let mut clocks = session.clocks.write().await;
+        if let Some(clocks) = clocks.as_mut() {
+            clocks.finish_turn(player_id);
+        }
         
         // Create messages for both players
         let mut messages = Vec::new();
-        
-        // Card played message
-        let card_played_msg = ServerMessage::CardPlayed {
+        let mut events = vec![GameEventType::CardPlayed {
             player: player_id,
             card,
-            captured_cards: result.captured_cards.clone(),
+            captured: result.captured_cards.clone(),
             is_kseri: result.is_kseri,
-        };
+        }];
         
-        // Send to both players
-        for p in session.players.iter().filter_map(|p| p.as_ref()) {
-            if p.connected {
-                messages.push((p.connection_id, card_played_msg.clone()));
+        // Card played message for every seat; face-down captures are only shown to the capturer
+        for p in session.players().iter().filter_map(|p| p.as_ref()) {
+            if p.bot.is_none() {
+                let visible_from = if p.player_id == player_id { 0 } else { result.hidden_captured };
+                let card_played_msg = ServerMessage::CardPlayed {
+                    player: player_id,
+                    card,
+                    captured_cards: result.captured_cards[visible_from..].to_vec(),
+                    hidden_captured: result.hidden_captured as u8,
+                    is_kseri: result.is_kseri,
+                };
+                messages.push((p.connection_id, card_played_msg));
             }
         }
+        let public_play = ServerMessage::CardPlayed {
+            player: player_id,
+            card,
+            captured_cards: result.captured_cards[result.hidden_captured..].to_vec(),
+            hidden_captured: result.hidden_captured as u8,
+            is_kseri: result.is_kseri,
+        };
+        messages.extend(session.spectators().iter().map(|conn| (*conn, public_play.clone())));
         
-        // Check if need to deal new cards
-        if game_state.player_hands[0].is_empty() && game_state.player_hands[1].is_empty() && !game_state.deck.is_empty() {
-            // Deal was already done in play_card, just notify players
-            for (idx, p) in session.players.iter().enumerate() {
+        // Notify players of the new hands dealt inside play_card
+        if result.cards_dealt {
+            events.push(GameEventType::CardsDealt {
+                hands: game_state.player_hands.clone(),
+                deck_remaining: game_state.deck.len(),
+            });
+            for (idx, p) in session.players().iter().enumerate() {
                 if let Some(player) = p {
-                    if player.connected {
+                    if player.bot.is_none() {
                         let new_cards_msg = ServerMessage::NewCards {
                             cards: game_state.player_hands[idx].clone(),
                         };
@@ -363,116 +1005,672 @@ impl SessionManager {
         }
         
         // Send state updates
-        for p in session.players.iter().filter_map(|p| p.as_ref()) {
-            if p.connected {
-                let state_msg = self.create_state_message(&game_state, p.player_id, &session);
+        for p in session.players().iter().filter_map(|p| p.as_ref()) {
+            if p.bot.is_none() {
+                let state_msg = self.create_state_message(&game_state, p.player_id, session, clocks.as_ref());
                 messages.push((p.connection_id, state_msg));
             }
         }
         
-        // Check game over
         if result.game_ended {
+            events.push(GameEventType::DealEnded {
+                winning_team: game_state.get_winning_team(),
+                scores: game_state.calculate_scores(),
+            });
+        }
+        
+        // Check game over; a finished game is rated once the locks are released
+        let mut finished = None;
+        let mut match_play = session.match_play.write().await;
+        let spectator_state = Self::spectator_state(session, &game_state, match_play.as_ref().map(Match::status));
+        messages.extend(session.spectators().iter().map(|conn| (*conn, spectator_state.clone())));
+        
+        if let (true, Some(current_match)) = (result.game_ended, match_play.as_mut()) {
+            // Fold the deal into the match, then either finish it or deal again
+            let result = current_match.record_deal(&game_state);
+            self.save_record(session, &game_state, format!("{}-deal{}.kseri", session.id, result.deal_number));
+            let summary = ServerMessage::DealSummary {
+                result,
+                match_status: current_match.status(),
+            };
+            messages.extend(Self::to_table(session, summary));
+            
+            if let Some(team) = current_match.winning_team() {
+                let winner = (!game_state.mode().is_partnership()).then_some(PlayerId(team as u8));
+                let match_over_msg = ServerMessage::MatchOver {
+                    winner,
+                    winning_team: team as u8,
+                    match_scores: current_match.scores.clone(),
+                    deals: current_match.deals.clone(),
+                };
+                
+                messages.extend(Self::to_table(session, match_over_msg));
+                finished = Some(Some(team));
+                
+                events.push(GameEventType::GameEnded(winner));
+            } else {
+                *game_state = current_match.start_deal(rand::random());
+                messages.extend(self.start_messages(session, &game_state, Some(current_match.status())).await);
+                events.push(Self::deal_started(&game_state));
+            }
+        } else if result.game_ended {
             let winner = game_state.get_winner();
-            let scores = game_state.calculate_scores();
+            self.save_record(session, &game_state, format!("{}.kseri", session.id));
             
             let game_over_msg = ServerMessage::GameOver {
                 winner,
-                final_scores: scores,
-                captured_cards: [
-                    game_state.player_scores[0].clone(),
-                    game_state.player_scores[1].clone(),
-                ],
+                winning_team: game_state.get_winning_team().map(|team| team as u8),
+                final_scores: game_state.calculate_scores(),
+                contributions: game_state.seat_points(),
+                captured_cards: game_state.player_scores.clone(),
+                seed: game_state.seed,
             };
             
-            for p in session.players.iter().filter_map(|p| p.as_ref()) {
-                if p.connected {
-                    messages.push((p.connection_id, game_over_msg.clone()));
-                }
+            messages.extend(Self::to_table(session, game_over_msg));
+            finished = Some(game_state.get_winning_team());
+            
+            events.push(GameEventType::GameEnded(winner));
+        }
+        
+        let mode = game_state.mode();
+        drop(match_play);
+        drop(clocks);
+        drop(game_state);
+        for event in events {
+            session.add_event(event).await;
+        }
+        
+        // Saving the ratings waits on the disk, which no play or view of the session should
+        if let Some(winning_team) = finished {
+            messages.extend(self.record_result(session, mode, winning_team).await);
+        }
+        
+        Ok(messages)
+    }
+    
+    /// Warn players who are running out of time and play for those who ran out.
+    /// Called periodically by the server; does nothing without a turn clock.
+    pub async fn enforce_turn_clocks(&self) -> Vec<(Uuid, ServerMessage)> {
+        if self.clock.is_none() {
+            return Vec::new();
+        }
+        
+        // Every table is checked at once, so a bot searching for one does not hold up the rest
+        let sessions: Vec<Arc<GameSession>> = self.sessions.iter().map(|entry| Arc::clone(entry.value())).collect();
+        join_all(sessions.iter().map(|session| self.check_turn_clock(session)))
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+    
+    async fn check_turn_clock(&self, session: &GameSession) -> Vec<(Uuid, ServerMessage)> {
+        let (seat, check, config, view, lowest) = {
+            let game_state = session.game_state.read().await;
+            let mut clocks = session.clocks.write().await;
+            let Some(clocks) = clocks.as_mut() else {
+                return Vec::new();
+            };
+            
+            // Bots answer straight away and are never timed
+            let seat = game_state.current_turn;
+            let human = session.players()[seat.index()].as_ref().is_some_and(|p| p.bot.is_none());
+            if game_state.game_over || !human {
+                return Vec::new();
             }
             
-            drop(game_state);
-            session.add_event(GameEventType::GameEnded(winner)).await;
-        } else {
-            drop(game_state);
-            session.add_event(GameEventType::CardPlayed(player_id, card)).await;
+            (seat, clocks.check(seat), clocks.config, game_state.player_view(seat), lowest_card(&game_state, seat))
+        };
+        
+        let mut messages = Vec::new();
+        match check {
+            ClockCheck::Running => {}
+            ClockCheck::Warning(remaining) => {
+                messages.extend(Self::to_table(session, ServerMessage::TimeWarning {
+                    player: seat,
+                    remaining_ms: remaining.as_millis() as u64,
+                }));
+            }
+            ClockCheck::Expired(timeouts) if timeouts >= config.max_timeouts => {
+                info!("Seat {:?} of session {} forfeits after {} timeouts", seat, session.id, timeouts);
+                messages.extend(self.forfeit(session, seat).await);
+                self.persist(session).await;
+            }
+            ClockCheck::Expired(timeouts) => {
+                messages.extend(Self::to_table(session, ServerMessage::TurnTimedOut {
+                    player: seat,
+                    timeouts,
+                    max_timeouts: config.max_timeouts,
+                }));
+                
+                let card = match config.auto_play {
+                    AutoPlay::LowestCard => lowest,
+                    AutoPlay::Bot => tokio::task::spawn_blocking(move || BotKind::default().create().choose_card(&view))
+                        .await
+                        .ok()
+                        .flatten(),
+                };
+                let Some(card) = card else {
+                    warn!("No card to auto-play for seat {:?} of session {}", seat, session.id);
+                    return messages;
+                };
+                
+                match self.apply_play(session, seat, card).await {
+                    Ok(played) => messages.extend(played),
+                    Err(e) => warn!("Auto-play for seat {:?} of session {} failed: {}", seat, session.id, e),
+                }
+                messages.extend(self.play_bot_turns(session).await);
+                self.persist(session).await;
+            }
+        }
+        
+        messages
+    }
+    
+    /// End the game with `seat` losing; the best-placed other team wins.
+    /// A running match ends too, decided by the match score.
+    async fn forfeit(&self, session: &GameSession, seat: PlayerId) -> Vec<(Uuid, ServerMessage)> {
+        let mut game_state = session.game_state.write().await;
+        let mut match_play = session.match_play.write().await;
+        let mode = game_state.mode();
+        
+        game_state.forfeit(seat);
+        let (winning_team, file_name) = match match_play.as_mut() {
+            Some(current_match) => {
+                current_match.forfeit(seat);
+                (current_match.winning_team(), format!("{}-deal{}.kseri", session.id, current_match.deal_number()))
+            }
+            None => (forfeit_winner(&game_state.calculate_scores(), mode, seat), format!("{}.kseri", session.id)),
+        };
+        let winner = winning_team.filter(|_| !mode.is_partnership()).map(|team| PlayerId(team as u8));
+        self.save_record(session, &game_state, file_name);
+        
+        let mut messages = Self::to_table(session, ServerMessage::Forfeit {
+            player: seat,
+            winning_team: winning_team.map(|team| team as u8),
+        });
+        if let (Some(current_match), Some(team)) = (match_play.as_ref(), winning_team) {
+            messages.extend(Self::to_table(session, ServerMessage::MatchOver {
+                winner,
+                winning_team: team as u8,
+                match_scores: current_match.scores.clone(),
+                deals: current_match.deals.clone(),
+            }));
         }
         
+        drop(match_play);
+        drop(game_state);
+        session.add_event(GameEventType::Forfeited { player: seat }).await;
+        session.add_event(GameEventType::GameEnded(winner)).await;
+        
+        messages.extend(self.record_result(session, mode, winning_team).await);
+        messages
+    }
+    
+    /// Send a chat line from the player on `connection_id` to the table and log it
+    pub async fn chat(
+        &self,
+        session_id: Uuid,
+        connection_id: Uuid,
+        text: &str,
+    ) -> Result<Vec<(Uuid, ServerMessage)>, Box<dyn std::error::Error + Send + Sync>> {
+        let session = self.session(&session_id)
+            .ok_or("Session not found")?;
+        let player = session.get_player_by_connection(connection_id)
+            .ok_or("Player not found in session")?;
+        let text = clean_chat(text)?;
+        
+        let msg = ServerMessage::Chat {
+            player: player.player_id,
+            name: player.name.clone(),
+            text: text.clone(),
+        };
+        self.speak(&session, player.player_id, msg, GameEventType::Chat { player: player.player_id, text }).await
+    }
+    
+    pub async fn emote(
+        &self,
+        session_id: Uuid,
+        connection_id: Uuid,
+        emote: Emote,
+    ) -> Result<Vec<(Uuid, ServerMessage)>, Box<dyn std::error::Error + Send + Sync>> {
+        let session = self.session(&session_id)
+            .ok_or("Session not found")?;
+        let player_id = session.get_player_by_connection(connection_id)
+            .ok_or("Player not found in session")?
+            .player_id;
+        
+        let msg = ServerMessage::Emote { player: player_id, emote };
+        self.speak(&session, player_id, msg, GameEventType::Emote { player: player_id, emote }).await
+    }
+    
+    /// Rate-limit `speaker`, then deliver `msg` to everyone who has not muted them
+    async fn speak(
+        &self,
+        session: &GameSession,
+        speaker: PlayerId,
+        msg: ServerMessage,
+        event: GameEventType,
+    ) -> Result<Vec<(Uuid, ServerMessage)>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut chat = session.chat.write().await;
+        if !chat.allow(speaker) {
+            return Err("Slow down, you are sending messages too fast".into());
+        }
+        
+        let messages = session.players().iter()
+            .filter_map(|p| p.as_ref())
+            .filter(|p| p.bot.is_none() && !chat.is_muted(p.player_id, speaker))
+            .map(|p| p.connection_id)
+            .chain(session.spectators().iter().copied())
+            .map(|conn| (conn, msg.clone()))
+            .collect();
+        drop(chat);
+        
+        session.add_event(event).await;
         Ok(messages)
     }
     
+    /// Hide or show chat and emotes from `player` for the player on `connection_id`
+    pub async fn mute(
+        &self,
+        session_id: Uuid,
+        connection_id: Uuid,
+        player: PlayerId,
+        muted: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let session = self.session(&session_id)
+            .ok_or("Session not found")?;
+        let listener = session.get_player_by_connection(connection_id)
+            .ok_or("Player not found in session")?
+            .player_id;
+        if player.index() >= session.seat_count() || player == listener {
+            return Err("No such opponent".into());
+        }
+        
+        session.chat.write().await.set_muted(listener, player, muted);
+        Ok(())
+    }
+    
+    /// Count a finished game in the table's head-to-head tally and show it to everyone.
+    /// Games where every seat is a rated human also update the ratings.
+    async fn record_result(&self, session: &GameSession, mode: GameMode, winning_team: Option<usize>) -> Vec<(Uuid, ServerMessage)> {
+        let mut tally = session.tally.write().await;
+        tally.record(winning_team);
+        let mut messages = Self::to_table(session, ServerMessage::HeadToHead { tally: tally.clone() });
+        drop(tally);
+        
+        let seats: Option<Vec<(String, usize)>> = session.players().iter()
+            .enumerate()
+            .map(|(seat, p)| {
+                let p = p.as_ref().filter(|p| p.bot.is_none())?;
+                Some((p.identity.clone()?, mode.team_of(seat)))
+            })
+            .collect();
+        let Some(seats) = seats else {
+            return messages;
+        };
+        
+        let before = self.seat_ratings(session).await;
+        match self.ratings.record_game(&seats, winning_team).await {
+            Ok(after) => {
+                for (seat, (old, new)) in before.iter().zip(&after).enumerate() {
+                    messages.extend(Self::to_table(session, ServerMessage::RatingChanged {
+                        player: PlayerId(seat as u8),
+                        old_rating: old.unwrap_or_default(),
+                        new_rating: new.display(),
+                    }));
+                }
+            }
+            Err(e) => warn!("Failed to save ratings for session {}: {}", session.id, e),
+        }
+        
+        messages
+    }
+    
+    /// Ratings indexed by seat; `None` for bots and players without an identity
+    async fn seat_ratings(&self, session: &GameSession) -> Vec<Option<u32>> {
+        let mut ratings = Vec::with_capacity(session.seat_count());
+        for player in &session.players() {
+            let identity = player.as_ref().filter(|p| p.bot.is_none()).and_then(|p| p.identity.as_deref());
+            let rating = match identity {
+                Some(identity) => self.ratings.get(identity).await.map(|rating| rating.display()),
+                None => None,
+            };
+            ratings.push(rating);
+        }
+        ratings
+    }
+    
+    /// Rated identity for a connection: the one it sent when known, otherwise a new one.
+    /// An account's identity is refused; it takes a login to play as the account.
+    pub async fn identify(&self, identity: Option<String>) -> Result<(String, u32), String> {
+        if let Some(identity) = identity {
+            if self.accounts.owns_identity(&identity).await {
+                return Err("This identity belongs to an account, log in instead".to_string());
+            }
+            if let Some(rating) = self.ratings.get(&identity).await {
+                return Ok((identity, rating.display()));
+            }
+        }
+        
+        let identity = Uuid::new_v4().to_string();
+        self.ratings.register(&identity, "").await;
+        Ok((identity, INITIAL_RATING as u32))
+    }
+    
+    /// Attach the connection's identity to its seat so the game is rated
+    pub async fn set_identity(&self, session_id: Uuid, connection_id: Uuid, identity: String) {
+        let name = {
+            let Some(session) = self.session(&session_id) else {
+                return;
+            };
+            let mut seats = session.seats();
+            let Some(player) = seats.iter_mut()
+                .filter_map(|p| p.as_mut())
+                .find(|p| p.connection_id == connection_id)
+            else {
+                return;
+            };
+            player.identity = Some(identity.clone());
+            player.name.clone()
+        };
+        
+        self.ratings.register(&identity, &name).await;
+    }
+    
+    /// Number the messages meant for seated players and keep them for replay, see `SeatOutbox`.
+    /// Every message for a seat should pass through here on its way out; messages for
+    /// spectators and unknown connections are returned unchanged.
+    pub async fn sequence(&self, messages: Vec<(Uuid, ServerMessage)>) -> Vec<(Uuid, ServerMessage)> {
+        let mut sequenced = Vec::with_capacity(messages.len());
+        for (connection_id, message) in messages {
+            let message = match self.seat_of(connection_id) {
+                Some((session, seat)) => session.outboxes.write().await[seat.index()].push(message),
+                None => message,
+            };
+            sequenced.push((connection_id, message));
+        }
+        sequenced
+    }
+    
+    /// The session and seat a connection plays in; spectators have none
+    fn seat_of(&self, connection_id: Uuid) -> Option<(Arc<GameSession>, PlayerId)> {
+        let session_id = *self.connection_to_session.get(&connection_id)?;
+        let session = self.session(&session_id)?;
+        let seat = session.get_player_by_connection(connection_id)?.player_id;
+        Some((session, seat))
+    }
+    
+    /// How the seat's earlier command with this id went, while it is still remembered
+    pub async fn command_outcome(&self, connection_id: Uuid, id: u64) -> Option<CommandOutcome> {
+        let (session, seat) = self.seat_of(connection_id)?;
+        let outcome = session.commands.read().await[seat.index()].outcome(id);
+        outcome
+    }
+    
+    /// Remember how a seated player's command went, across reconnects.
+    /// Returns false when the connection has no seat.
+    pub async fn record_command(&self, connection_id: Uuid, id: u64, outcome: CommandOutcome) -> bool {
+        let Some((session, seat)) = self.seat_of(connection_id) else {
+            return false;
+        };
+        session.commands.write().await[seat.index()].record(id, outcome);
+        true
+    }
+    
+    /// Optional features this server offers, reported in `Welcome`
+    pub fn capabilities(&self) -> Vec<Capability> {
+        Capability::all().into_iter()
+            .filter(|capability| *capability != Capability::TurnClock || self.clock.is_some())
+            .collect()
+    }
+    
+    pub async fn leaderboard(&self, limit: usize) -> Vec<LeaderboardEntry> {
+        self.ratings.leaderboard(limit).await
+    }
+    
+    pub async fn register_account(&self, username: &str, password: &str) -> Result<(Login, u32), String> {
+        let login = self.accounts.register(username, password).await?;
+        Ok(self.logged_in(login).await)
+    }
+    
+    pub async fn login(&self, username: &str, password: &str) -> Result<(Login, u32), String> {
+        let login = self.accounts.login(username, password).await?;
+        Ok(self.logged_in(login).await)
+    }
+    
+    pub async fn login_with_token(&self, token: &str) -> Result<(Login, u32), String> {
+        let login = self.accounts.login_with_token(token).await?;
+        Ok(self.logged_in(login).await)
+    }
+    
+    /// Rate the account under its username and look up its rating
+    async fn logged_in(&self, login: Login) -> (Login, u32) {
+        self.ratings.register(&login.identity, &login.username).await;
+        let rating = self.ratings.get(&login.identity).await
+            .map_or(INITIAL_RATING as u32, |rating| rating.display());
+        (login, rating)
+    }
+    
+    /// Vote for a rematch of the finished game. `accept` answers someone else's request.
+    /// Bots always agree; once every human seat has voted the new game starts.
+    pub async fn rematch(
+        &self,
+        session_id: Uuid,
+        connection_id: Uuid,
+        accept: bool,
+    ) -> Result<Vec<(Uuid, ServerMessage)>, Box<dyn std::error::Error + Send + Sync>> {
+        let session = self.session(&session_id)
+            .ok_or("Session not found")?;
+        let player_id = session.get_player_by_connection(connection_id)
+            .ok_or("Player not found in session")?
+            .player_id;
+        
+        if !session.game_state.read().await.game_over {
+            return Err("The game is not over yet".into());
+        }
+        
+        let mut votes = session.rematch_votes.write().await;
+        if accept && votes.is_empty() {
+            return Err("Nobody asked for a rematch".into());
+        }
+        if !votes.contains(&player_id) {
+            votes.push(player_id);
+        }
+        
+        let everyone_agrees = session.players().iter()
+            .filter_map(|p| p.as_ref())
+            .filter(|p| p.bot.is_none())
+            .all(|p| votes.contains(&p.player_id));
+        if !everyone_agrees {
+            let msg = ServerMessage::RematchRequested { player: player_id };
+            return Ok(session.other_players(player_id).into_iter()
+                .filter(|p| p.bot.is_none())
+                .map(|p| (p.connection_id, msg.clone()))
+                .collect());
+        }
+        votes.clear();
+        drop(votes);
+        
+        session.start_rematch(rand::random()).await;
+        *session.clocks.write().await = self.new_clocks(&session);
+        info!("Session {} starts a rematch", session_id);
+        
+        let tally = session.tally.read().await.clone();
+        let mut messages = Self::to_table(&session, ServerMessage::HeadToHead { tally });
+        let game_state = session.game_state.read().await;
+        let match_status = session.match_play.read().await.as_ref().map(Match::status);
+        messages.extend(self.start_messages(&session, &game_state, match_status).await);
+        let deal_started = Self::deal_started(&game_state);
+        drop(game_state);
+        
+        session.add_event(deal_started).await;
+        messages.extend(self.play_bot_turns(&session).await);
+        self.persist(&session).await;
+        
+        Ok(messages)
+    }
+    
+    /// `msg` for every human seat, online or not so it can be replayed, and everyone watching
+    fn to_table(session: &GameSession, msg: ServerMessage) -> Vec<(Uuid, ServerMessage)> {
+        session.players().iter()
+            .filter_map(|p| p.as_ref())
+            .filter(|p| p.bot.is_none())
+            .map(|p| p.connection_id)
+            .chain(session.spectators().iter().copied())
+            .map(|conn| (conn, msg.clone()))
+            .collect()
+    }
+    
     pub async fn get_state_for_player(&self, session_id: Uuid, connection_id: Uuid) -> Option<ServerMessage> {
-        let session = self.sessions.get(&session_id)?;
+        let session = self.session(&session_id)?;
+        let game_state = session.game_state.read().await;
+        
+        if session.spectators().contains(&connection_id) {
+            let match_status = session.match_play.read().await.as_ref().map(Match::status);
+            return Some(Self::spectator_state(&session, &game_state, match_status));
+        }
+        
         let player = session.get_player_by_connection(connection_id)?;
+        let clocks = session.clocks.read().await;
+        
+        Some(self.create_state_message(&game_state, player.player_id, &session, clocks.as_ref()))
+    }
+    
+    /// Text record of the finished deal, for a player seated in the session
+    pub async fn game_record(&self, session_id: Uuid, connection_id: Uuid) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let session = self.session(&session_id)
+            .ok_or("Session not found")?;
+        session.get_player_by_connection(connection_id)
+            .ok_or("Player not found in session")?;
+        
         let game_state = session.game_state.read().await;
+        let record = GameRecord::from_game(&game_state, session.player_names(), record_date())?;
+        
+        Ok(record.to_string())
+    }
+    
+    /// Full event log of a session, for audits and dispute resolution
+    pub async fn event_log(&self, session_id: Uuid) -> Option<GameLog> {
+        let session = self.session(&session_id)?;
+        let log = session.event_history.read().await.clone();
+        Some(log)
+    }
+    
+    /// Write a finished deal to the record directory, when one is configured.
+    /// The file is written in the background, so the session's locks never wait on the disk.
+    fn save_record(&self, session: &GameSession, game_state: &KseriGameState, file_name: String) {
+        let Some(dir) = &self.record_dir else {
+            return;
+        };
         
-        Some(self.create_state_message(&game_state, player.player_id, &session))
+        let path = dir.join(file_name);
+        let text = match GameRecord::from_game(game_state, session.player_names(), record_date()) {
+            Ok(record) => record.to_string(),
+            Err(e) => {
+                warn!("Failed to save game record {}: {}", path.display(), e);
+                return;
+            }
+        };
+        tokio::spawn(async move {
+            match tokio::fs::write(&path, text).await {
+                Ok(()) => info!("Saved game record {}", path.display()),
+                Err(e) => warn!("Failed to save game record {}: {}", path.display(), e),
+            }
+        });
     }
     
-    fn create_state_message(&self, game_state: &KseriGameState, player_id: PlayerId, _session: &GameSession) -> ServerMessage {
-        let player_idx = player_id.0 as usize;
-        let opponent_idx = 1 - player_idx;
-        let scores = game_state.calculate_scores();
+    fn create_state_message(&self, game_state: &KseriGameState, player_id: PlayerId, session: &GameSession, clocks: Option<&TurnClocks>) -> ServerMessage {
+        let mode = game_state.mode();
+        let player_idx = player_id.index();
+        let next_idx = player_id.next(game_state.seat_count()).index();
+        let team_scores = game_state.calculate_scores();
+        let (my_team, next_team) = (mode.team_of(player_idx), mode.team_of(next_idx));
         
         ServerMessage::StateUpdate {
             hand_count: game_state.player_hands[player_idx].len() as u8,
-            opponent_hand_count: game_state.player_hands[opponent_idx].len() as u8,
-            table_cards: game_state.table_cards.clone(),
-            your_score: scores[player_idx],
-            opponent_score: scores[opponent_idx],
+            opponent_hand_count: game_state.player_hands[next_idx].len() as u8,
+            table_cards: game_state.visible_table_cards().to_vec(),
+            hidden_table_cards: game_state.table_hidden as u8,
+            your_score: team_scores[my_team],
+            opponent_score: team_scores[next_team],
             deck_remaining: game_state.deck.len() as u8,
             your_turn: game_state.current_turn == player_id,
             last_capture_player: game_state.last_capture_player,
+            hand_counts: game_state.player_hands.iter().map(|hand| hand.len() as u8).collect(),
+            team_scores,
+            contributions: game_state.seat_points(),
+            turn_time_remaining_ms: clocks.filter(|_| !game_state.game_over)
+                .map(|clocks| clocks.remaining(game_state.current_turn).as_millis() as u64),
+            time_banks_ms: clocks.map(|clocks| clocks.banks().iter().map(|bank| bank.as_millis() as u64).collect())
+                .unwrap_or_default(),
+            spectators: session.spectators().len() as u32,
         }
     }
     
-    pub async fn handle_player_disconnect(&self, session_id: Uuid, connection_id: Uuid) -> Option<(Uuid, ServerMessage)> {
-        let mut session = self.sessions.get_mut(&session_id)?;
+    pub async fn handle_player_disconnect(&self, session_id: Uuid, connection_id: Uuid) -> Vec<(Uuid, ServerMessage)> {
+        let mut messages = Vec::new();
+        let Some(session) = self.session(&session_id) else {
+            return messages;
+        };
+        
+        // A spectator leaving only changes the count the players see
+        if session.remove_spectator(connection_id) {
+            self.connection_to_session.remove(&connection_id);
+            return Self::spectators_changed(&session);
+        }
         
         // Find and update player
-        let player_idx = session.players.iter()
-            .position(|p| p.as_ref().map(|p| p.connection_id == connection_id).unwrap_or(false))?;
+        let player_id = {
+            let mut seats = session.seats();
+            let Some(player) = seats.iter_mut()
+                .filter_map(|p| p.as_mut())
+                .find(|p| p.connection_id == connection_id)
+            else {
+                return messages;
+            };
             
-        if let Some(player) = &mut session.players[player_idx] {
             player.connected = false;
             player.disconnected_at = Some(Instant::now());
-            
-            let player_id = player.player_id;
-            
-            // Remove from connection mapping
-            self.connection_to_session.remove(&connection_id);
-            
-            // Notify opponent
-            if let Some(opponent) = session.get_opponent(player_id) {
-                if opponent.connected {
-                    let msg = ServerMessage::OpponentDisconnected {
-                        timeout_seconds: RECONNECT_TIMEOUT.as_secs() as u32,
-                    };
-                    
-                    // Add event asynchronously
-                    let session_id = session.id;
-                    let sessions = self.sessions.clone();
-                    tokio::spawn(async move {
-                        if let Some(s) = sessions.get(&session_id) {
-                            s.add_event(GameEventType::PlayerDisconnected(player_id)).await;
-                        }
-                    });
-                    
-                    return Some((opponent.connection_id, msg));
-                }
-            }
+            player.player_id
+        };
+        
+        // The connection stays mapped to the seat until it reconnects or the session ends,
+        // so messages for the seat are still numbered and buffered in the meantime
+        
+        // Notify everyone still at the table
+        let msg = ServerMessage::OpponentDisconnected {
+            timeout_seconds: RECONNECT_TIMEOUT.as_secs() as u32,
+        };
+        for other in session.other_players(player_id).into_iter().filter(|p| p.bot.is_none()) {
+            messages.push((other.connection_id, msg.clone()));
         }
         
-        None
+        if !messages.is_empty() {
+            // Add event asynchronously
+            tokio::spawn(async move {
+                session.add_event(GameEventType::PlayerDisconnected(player_id)).await;
+            });
+        }
+        
+        messages
     }
     
-    pub async fn get_opponent_connection(&self, session_id: Uuid, player_id: PlayerId) -> Option<Uuid> {
-        let session = self.sessions.get(&session_id)?;
-        session.get_opponent(player_id)
+    /// Connections of everyone else at the table who is currently online
+    pub async fn get_other_connections(&self, session_id: Uuid, player_id: PlayerId) -> Vec<Uuid> {
+        let Some(session) = self.session(&session_id) else {
+            return Vec::new();
+        };
+        
+        session.other_players(player_id).into_iter()
             .filter(|p| p.connected)
             .map(|p| p.connection_id)
+            .collect()
     }
     
-    pub async fn cleanup_expired_sessions(&self) {
+    /// Drop finished or abandoned sessions, and rooms and queued tables that did not fill in
+    /// time. Returns a `RoomClosed` for each player still seated at a closed table.
+    pub async fn cleanup_expired_sessions(&self) -> Vec<(Uuid, ServerMessage)> {
         let expired: Vec<Uuid> = self.sessions.iter()
             .filter(|entry| entry.value().is_expired())
             .map(|entry| *entry.key())
@@ -481,16 +1679,766 @@ impl SessionManager {
         for session_id in expired {
             if let Some((_, session)) = self.sessions.remove(&session_id) {
                 // Remove connection mappings
-                for player in session.players.iter().filter_map(|p| p.as_ref()) {
+                for player in session.players().iter().filter_map(|p| p.as_ref()) {
                     self.connection_to_session.remove(&player.connection_id);
                 }
+                for spectator in &session.spectators() {
+                    self.connection_to_session.remove(spectator);
+                }
+                
+                if let Some(store) = &self.store {
+                    if let Err(e) = store.remove(session_id).await {
+                        warn!("Failed to remove snapshot of session {}: {}", session_id, e);
+                    }
+                }
                 
                 info!("Cleaned up expired session {}", session_id);
             }
         }
         
-        // Clean up waiting queue
-        let mut queue = self.waiting_queue.write().await;
-        queue.retain(|player| player.joined_at.elapsed() < WAITING_TIMEOUT);
+        // Close rooms nobody joined in time and tell whoever sat down
+        let mut closed = Vec::new();
+        let stale_rooms: Vec<(String, Uuid)> = self.rooms.iter()
+            .filter(|room| room.created_at.elapsed() > ROOM_TIMEOUT)
+            .map(|room| (room.key().clone(), room.session_id))
+            .collect();
+        for (code, session_id) in stale_rooms {
+            self.rooms.remove(&code);
+            closed.extend(self.close_unfilled(session_id, Some(code.clone())));
+            info!("Closed room {}, nobody joined", code);
+        }
+        
+        // The same for public tables that waited too long in the queue
+        let mut stale_tables = Vec::new();
+        self.waiting_queue.write().await.retain(|waiting| {
+            let waited_out = waiting.joined_at.elapsed() >= WAITING_TIMEOUT;
+            if waited_out {
+                stale_tables.push(waiting.session_id);
+            }
+            !waited_out
+        });
+        for session_id in stale_tables {
+            closed.extend(self.close_unfilled(session_id, None));
+            info!("Closed session {}, nobody joined from the queue", session_id);
+        }
+        
+        closed
+    }
+    
+    /// Remove a table that never filled and free its seats; returns a `RoomClosed` for every
+    /// connected player. `code` is the invite code of a private room.
+    fn close_unfilled(&self, session_id: Uuid, code: Option<String>) -> Vec<(Uuid, ServerMessage)> {
+        let Some((_, session)) = self.sessions.remove(&session_id) else {
+            return Vec::new();
+        };
+        
+        let mut closed = Vec::new();
+        for player in session.players().iter().filter_map(|p| p.as_ref()) {
+            self.connection_to_session.remove(&player.connection_id);
+            if player.connected && player.bot.is_none() {
+                closed.push((player.connection_id, ServerMessage::RoomClosed { code: code.clone() }));
+            }
+        }
+        for spectator in &session.spectators() {
+            self.connection_to_session.remove(spectator);
+        }
+        closed
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::card::Rank;
+    use crate::server::chat::MAX_CHAT_LEN;
+    use crate::server::commands::NackReason;
+    use crate::server::rules::{GameMode, RuleSet, RuleVariant};
+    
+    #[tokio::test]
+    async fn test_matchmaking_pairs_same_variant() {
+        let manager = SessionManager::new();
+        let (classic, pisti, classic_again) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        
+        let classic_config = GameConfig { variant: RuleVariant::Classic, ..Default::default() };
+        let pisti_config = GameConfig { variant: RuleVariant::Pisti, ..Default::default() };
+        
+        let (classic_session, _, _) = manager.join_or_create_game(classic, "A".to_string(), classic_config).await.unwrap();
+        let (pisti_session, _, _) = manager.join_or_create_game(pisti, "B".to_string(), pisti_config).await.unwrap();
+        assert_ne!(classic_session, pisti_session);
+        assert!(manager.check_game_ready(pisti_session).await.is_none());
+        
+        // The third player joins the first Classic player's session, not the Pişti one
+        let (session_id, player_id, _) = manager.join_or_create_game(classic_again, "C".to_string(), classic_config).await.unwrap();
+        assert_eq!(session_id, classic_session);
+        assert_eq!(player_id, PlayerId::PLAYER_TWO);
+        
+        let start = manager.check_game_ready(session_id).await.unwrap();
+        assert_eq!(start.len(), 2);
+        assert!(matches!(start[0].1, ServerMessage::GameStarted { variant: RuleVariant::Classic, .. }));
+    }
+    
+    #[tokio::test]
+    async fn test_partnership_session_fills_four_seats() {
+        let manager = SessionManager::new();
+        let config = GameConfig { mode: GameMode::Partnership, ..Default::default() };
+        
+        let mut session_ids = Vec::new();
+        for (seat, name) in ["N", "E", "S", "W"].into_iter().enumerate() {
+            let (session_id, player_id, _) = manager.join_or_create_game(Uuid::new_v4(), name.to_string(), config).await.unwrap();
+            assert_eq!(player_id, PlayerId(seat as u8));
+            session_ids.push(session_id);
+            
+            let ready = manager.check_game_ready(session_id).await;
+            assert_eq!(ready.is_some(), seat == 3);
+        }
+        assert!(session_ids.iter().all(|id| *id == session_ids[0]));
+        
+        // A fifth player starts a new table
+        let (fifth_session, _, _) = manager.join_or_create_game(Uuid::new_v4(), "X".to_string(), config).await.unwrap();
+        assert_ne!(fifth_session, session_ids[0]);
+        
+        let start = manager.check_game_ready(session_ids[0]).await.unwrap();
+        assert_eq!(start.len(), 4);
+        for (_, msg) in &start {
+            match msg {
+                ServerMessage::GameStarted { initial_hand, player_names, mode, .. } => {
+                    assert_eq!(initial_hand.len(), 4);
+                    assert_eq!(player_names, &["N", "E", "S", "W"]);
+                    assert_eq!(*mode, GameMode::Partnership);
+                }
+                other => panic!("Unexpected message {:?}", other),
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_three_player_table() {
+        let manager = SessionManager::new();
+        let config = GameConfig { mode: GameMode::ThreePlayer, ..Default::default() };
+        
+        let mut connections = Vec::new();
+        let mut session_id = Uuid::nil();
+        for name in ["A", "B", "C"] {
+            let connection_id = Uuid::new_v4();
+            (session_id, _, _) = manager.join_or_create_game(connection_id, name.to_string(), config).await.unwrap();
+            connections.push(connection_id);
+        }
+        assert_eq!(manager.check_game_ready(session_id).await.unwrap().len(), 3);
+        
+        // Seat 0 plays; everyone sees the card and gets a fresh state with three hand counts
+        let card = manager.session(&session_id).unwrap().game_state.read().await.player_hands[0][0];
+        let messages = manager.play_card(session_id, connections[0], card).await.unwrap();
+        let state_updates: Vec<_> = messages.iter()
+            .filter_map(|(conn, msg)| match msg {
+                ServerMessage::StateUpdate { hand_counts, your_turn, .. } => Some((*conn, hand_counts.clone(), *your_turn)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(state_updates.len(), 3);
+        for (conn, hand_counts, your_turn) in state_updates {
+            assert_eq!(hand_counts, vec![3, 4, 4]);
+            assert_eq!(your_turn, conn == connections[1]);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_match_deals_until_target() {
+        let manager = SessionManager::new();
+        let config = GameConfig { target_score: Some(30), ..Default::default() };
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        
+        let (session_id, _, _) = manager.join_or_create_game(p1, "A".to_string(), config).await.unwrap();
+        manager.join_or_create_game(p2, "B".to_string(), config).await.unwrap();
+        let start = manager.check_game_ready(session_id).await.unwrap();
+        assert!(matches!(&start[0].1, ServerMessage::GameStarted { match_status: Some(status), .. } if status.deal_number == 1));
+        
+        let mut summaries = 0;
+        let mut match_over = None;
+        while match_over.is_none() {
+            let (player_id, card) = {
+                let session = manager.session(&session_id).unwrap();
+                let state = session.game_state.read().await;
+                (state.current_turn, state.player_hands[state.current_turn.index()][0])
+            };
+            let connection = if player_id == PlayerId::PLAYER_ONE { p1 } else { p2 };
+            
+            for (_, msg) in manager.play_card(session_id, connection, card).await.unwrap() {
+                match msg {
+                    ServerMessage::DealSummary { .. } => summaries += 1,
+                    ServerMessage::MatchOver { match_scores, deals, .. } => match_over = Some((match_scores, deals)),
+                    _ => {}
+                }
+            }
+        }
+        
+        // Both players get every summary; the last deal pushed someone past the target
+        let (match_scores, deals) = match_over.unwrap();
+        assert_eq!(summaries, deals.len() * 2);
+        assert!(match_scores.iter().any(|score| *score >= 30));
+        assert_eq!(deals[0].dealer, PlayerId::PLAYER_TWO);
+    }
+    
+    #[tokio::test]
+    async fn test_face_down_table_cards_stay_hidden() {
+        let manager = SessionManager::new();
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let (session_id, _, _) = manager.join_or_create_game(p1, "A".to_string(), GameConfig::default()).await.unwrap();
+        manager.join_or_create_game(p2, "B".to_string(), GameConfig::default()).await.unwrap();
+        
+        for (_, msg) in manager.check_game_ready(session_id).await.unwrap() {
+            match msg {
+                ServerMessage::GameStarted { table_cards, hidden_table_cards, .. } => {
+                    assert_eq!(table_cards.len(), 1);
+                    assert_eq!(hidden_table_cards, 3);
+                }
+                other => panic!("Unexpected message {:?}", other),
+            }
+        }
+        
+        // Deal from a seed where player one holds a Jack, so the opening table gets captured
+        let (jack, face_down) = {
+            let session = manager.session(&session_id).unwrap();
+            let mut state = session.game_state.write().await;
+            *state = (0..).map(KseriGameState::with_seed)
+                .find(|s| s.player_hands[0].iter().any(|c| c.rank == Rank::Jack))
+                .unwrap();
+            let jack = *state.player_hands[0].iter().find(|c| c.rank == Rank::Jack).unwrap();
+            (jack, state.table_cards[..3].to_vec())
+        };
+        
+        for (conn, msg) in manager.play_card(session_id, p1, jack).await.unwrap() {
+            if let ServerMessage::CardPlayed { captured_cards, hidden_captured, .. } = msg {
+                assert_eq!(hidden_captured, 3);
+                if conn == p1 {
+                    assert_eq!(&captured_cards[..3], &face_down[..]);
+                } else {
+                    assert!(face_down.iter().all(|c| !captured_cards.contains(c)));
+                }
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_bot_game_answers_every_play() {
+        let manager = SessionManager::new();
+        let human = Uuid::new_v4();
+        let config = GameConfig { mode: GameMode::ThreePlayer, ..Default::default() };
+        
+        let (session_id, player_id, _) = manager.create_bot_game(human, "A".to_string(), config, BotKind::Heuristic).await.unwrap();
+        assert_eq!(player_id, PlayerId::PLAYER_ONE);
+        
+        let start = manager.check_game_ready(session_id).await.unwrap();
+        assert!(start.iter().all(|(conn, _)| *conn == human));
+        
+        // Bots move straight after each human play, so it is always the human's turn again
+        let mut game_over = false;
+        while !game_over {
+            let card = {
+                let session = manager.session(&session_id).unwrap();
+                let state = session.game_state.read().await;
+                assert_eq!(state.current_turn, player_id);
+                state.player_hands[0][0]
+            };
+            
+            let messages = manager.play_card(session_id, human, card).await.unwrap();
+            let plays = messages.iter().filter(|(_, msg)| matches!(msg, ServerMessage::CardPlayed { .. })).count();
+            game_over = messages.iter().any(|(_, msg)| matches!(msg, ServerMessage::GameOver { .. }));
+            assert!(game_over || plays == 3);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_waiting_table_falls_back_to_bots() {
+        let manager = SessionManager::new().with_bot_fallback(Duration::ZERO);
+        let human = Uuid::new_v4();
+        
+        let (session_id, _, _) = manager.join_or_create_game(human, "A".to_string(), GameConfig::default()).await.unwrap();
+        assert!(manager.check_game_ready(session_id).await.is_none());
+        
+        let messages = manager.fill_waiting_with_bots().await;
+        assert!(matches!(&messages[0], (conn, ServerMessage::GameStarted { opponent_name, .. }) if *conn == human && opponent_name == "Bot"));
+        
+        // The table is no longer open to other players
+        let (other_session, _, _) = manager.join_or_create_game(Uuid::new_v4(), "B".to_string(), GameConfig::default()).await.unwrap();
+        assert_ne!(other_session, session_id);
+    }
+    
+    #[tokio::test]
+    async fn test_record_available_after_game_over() {
+        let manager = SessionManager::new();
+        let human = Uuid::new_v4();
+        let (session_id, _, _) = manager.create_bot_game(human, "A".to_string(), GameConfig::default(), BotKind::Heuristic).await.unwrap();
+        manager.check_game_ready(session_id).await.unwrap();
+        assert!(manager.game_record(session_id, human).await.is_err());
+        
+        let mut game_over = false;
+        while !game_over {
+            let card = manager.session(&session_id).unwrap().game_state.read().await.player_hands[0][0];
+            let messages = manager.play_card(session_id, human, card).await.unwrap();
+            game_over = messages.iter().any(|(_, msg)| matches!(msg, ServerMessage::GameOver { .. }));
+        }
+        
+        // Only players at the table may download it, and it replays to the same game
+        assert!(manager.game_record(session_id, Uuid::new_v4()).await.is_err());
+        let text = manager.game_record(session_id, human).await.unwrap();
+        let record = GameRecord::parse(&text).unwrap();
+        assert_eq!(record.players, vec!["A".to_string(), "Bot".to_string()]);
+        
+        let replayed = record.replay().unwrap().pop().unwrap();
+        let session = manager.session(&session_id).unwrap();
+        assert_eq!(replayed.move_history, session.game_state.read().await.move_history);
+    }
+    
+    #[tokio::test]
+    async fn test_event_log_rebuilds_game() {
+        let manager = SessionManager::new();
+        let human = Uuid::new_v4();
+        let (session_id, _, _) = manager.create_bot_game(human, "A".to_string(), GameConfig::default(), BotKind::Heuristic).await.unwrap();
+        manager.check_game_ready(session_id).await.unwrap();
+        
+        let mut game_over = false;
+        while !game_over {
+            let card = manager.session(&session_id).unwrap().game_state.read().await.player_hands[0][0];
+            let messages = manager.play_card(session_id, human, card).await.unwrap();
+            game_over = messages.iter().any(|(_, msg)| matches!(msg, ServerMessage::GameOver { .. }));
+        }
+        
+        // Nothing was trimmed: both joins, the deal, every play and the end are all there
+        let log = manager.event_log(session_id).await.unwrap();
+        let session = manager.session(&session_id).unwrap();
+        let state = session.game_state.read().await;
+        let plays = log.events().iter().filter(|event| matches!(event.event_type, GameEventType::CardPlayed { .. })).count();
+        assert_eq!(plays, state.move_history.len());
+        assert!(log.len() > 50);
+        assert!(matches!(log.events()[2].event_type, GameEventType::DealStarted { seed, .. } if seed == state.seed));
+        assert!(matches!(log.events().last().unwrap().event_type, GameEventType::GameEnded(_)));
+        
+        let rebuilt = log.rebuild().unwrap();
+        assert_eq!(rebuilt.move_history, state.move_history);
+        assert_eq!(rebuilt.calculate_scores(), state.calculate_scores());
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_turn_clock_auto_plays_then_forfeits() {
+        let clock = ClockConfig { per_move: Duration::ZERO, max_timeouts: 3, ..Default::default() };
+        let manager = SessionManager::new().with_turn_clock(clock);
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let (session_id, _, _) = manager.join_or_create_game(p1, "A".to_string(), GameConfig::default()).await.unwrap();
+        manager.join_or_create_game(p2, "B".to_string(), GameConfig::default()).await.unwrap();
+        manager.check_game_ready(session_id).await.unwrap();
+        
+        let state = manager.get_state_for_player(session_id, p1).await.unwrap();
+        assert!(matches!(state, ServerMessage::StateUpdate { turn_time_remaining_ms: Some(0), .. }));
+        
+        // Both players walk away: every check plays the lowest card for whoever is to move
+        for round in 1..=2 {
+            for seat in [PlayerId::PLAYER_ONE, PlayerId::PLAYER_TWO] {
+                let messages = manager.enforce_turn_clocks().await;
+                assert!(messages.iter().any(|(_, msg)| matches!(msg,
+                    ServerMessage::TurnTimedOut { player, timeouts, .. } if *player == seat && *timeouts == round)));
+                assert!(messages.iter().any(|(_, msg)| matches!(msg, ServerMessage::CardPlayed { player, .. } if *player == seat)));
+            }
+        }
+        
+        // The third timeout in a row loses the game
+        let messages = manager.enforce_turn_clocks().await;
+        assert_eq!(messages.len(), 4);
+        assert!(matches!(messages[0].1, ServerMessage::Forfeit { player: PlayerId::PLAYER_ONE, winning_team: Some(1) }));
+        assert!(matches!(&messages[3].1, ServerMessage::HeadToHead { tally } if tally.wins == vec![0, 1]));
+        assert!(manager.enforce_turn_clocks().await.is_empty());
+        
+        let log = manager.event_log(session_id).await.unwrap();
+        assert!(matches!(log.events().last().unwrap().event_type, GameEventType::GameEnded(Some(PlayerId::PLAYER_TWO))));
+        
+        // The log and the record both end the game where the snapshot does
+        let session = manager.session(&session_id).unwrap();
+        let state = session.game_state.read().await;
+        let rebuilt = log.rebuild().unwrap();
+        assert!(rebuilt.game_over);
+        assert_eq!(rebuilt.forfeited, Some(PlayerId::PLAYER_ONE));
+        assert_eq!(rebuilt.move_history, state.move_history);
+        assert_eq!(rebuilt.calculate_scores(), state.calculate_scores());
+        
+        let record = GameRecord::parse(&manager.game_record(session_id, p1).await.unwrap()).unwrap();
+        assert_eq!(record.forfeited, Some(PlayerId::PLAYER_ONE));
+        assert_eq!(record.replay().unwrap().pop().unwrap().move_history, state.move_history);
+    }
+    
+    #[tokio::test]
+    async fn test_forfeit_ends_the_match() {
+        let clock = ClockConfig { per_move: Duration::ZERO, max_timeouts: 1, ..Default::default() };
+        let manager = SessionManager::new().with_turn_clock(clock);
+        let config = GameConfig { target_score: Some(101), ..Default::default() };
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let (session_id, _, _) = manager.join_or_create_game(p1, "A".to_string(), config).await.unwrap();
+        manager.join_or_create_game(p2, "B".to_string(), config).await.unwrap();
+        manager.check_game_ready(session_id).await.unwrap();
+        
+        let messages = manager.enforce_turn_clocks().await;
+        assert!(matches!(messages[0].1, ServerMessage::Forfeit { player: PlayerId::PLAYER_ONE, .. }));
+        assert!(messages.iter().any(|(_, msg)| matches!(msg, ServerMessage::MatchOver { winning_team: 1, .. })));
+        
+        let session = manager.session(&session_id).unwrap();
+        let match_play = session.match_play.read().await;
+        let current_match = match_play.as_ref().unwrap();
+        assert!(current_match.is_over());
+        assert_eq!(current_match.forfeited, Some(PlayerId::PLAYER_ONE));
+        assert!(session.game_state.read().await.game_over);
+    }
+    
+    #[tokio::test]
+    async fn test_spectators_never_see_hands() {
+        let manager = SessionManager::new();
+        let (p1, p2, watcher) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let (session_id, _, _) = manager.join_or_create_game(p1, "A".to_string(), GameConfig::default()).await.unwrap();
+        manager.join_or_create_game(p2, "B".to_string(), GameConfig::default()).await.unwrap();
+        
+        let (state, notified) = manager.spectate(watcher, session_id).await.unwrap();
+        assert!(matches!(state, ServerMessage::SpectatorState { spectators: 1, .. }));
+        assert_eq!(notified.len(), 2);
+        assert!(notified.iter().all(|(_, msg)| matches!(msg, ServerMessage::SpectatorsChanged { count: 1 })));
+        // A spectator cannot also take a seat or play
+        assert!(manager.join_or_create_game(watcher, "C".to_string(), GameConfig::default()).await.is_err());
+        
+        let start = manager.check_game_ready(session_id).await.unwrap();
+        let watched: Vec<_> = start.iter().filter(|(conn, _)| *conn == watcher).collect();
+        assert_eq!(watched.len(), 1);
+        assert!(matches!(watched[0].1, ServerMessage::SpectatorState { .. }));
+        
+        let card = manager.session(&session_id).unwrap().game_state.read().await.player_hands[0][0];
+        assert!(manager.play_card(session_id, watcher, card).await.is_err());
+        let messages = manager.play_card(session_id, p1, card).await.unwrap();
+        for (_, msg) in messages.iter().filter(|(conn, _)| *conn == watcher) {
+            assert!(matches!(msg, ServerMessage::CardPlayed { .. } | ServerMessage::SpectatorState { .. }));
+        }
+        assert!(messages.iter().any(|(conn, msg)| *conn == p2 && matches!(msg, ServerMessage::StateUpdate { spectators: 1, .. })));
+        
+        // Leaving updates the count for the players
+        let left = manager.handle_player_disconnect(session_id, watcher).await;
+        assert!(left.iter().all(|(_, msg)| matches!(msg, ServerMessage::SpectatorsChanged { count: 0 })));
+        assert!(manager.session(&session_id).unwrap().spectators().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_oversized_custom_rules_are_refused() {
+        let manager = SessionManager::new();
+        let rules = RuleSet { kseri_bonus: u32::MAX, ..RuleVariant::Classic.rules() };
+        let config = GameConfig { variant: RuleVariant::Custom(rules), ..Default::default() };
+        
+        assert!(manager.join_or_create_game(Uuid::new_v4(), "A".to_string(), config).await.is_err());
+        assert!(manager.create_room(Uuid::new_v4(), "A".to_string(), config).await.is_err());
+        assert!(manager.create_bot_game(Uuid::new_v4(), "A".to_string(), config, BotKind::default()).await.is_err());
+        assert!(manager.sessions.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_private_room_by_invite_code() {
+        let manager = SessionManager::new();
+        let (host, friend, stranger) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let config = GameConfig { variant: RuleVariant::Pisti, ..Default::default() };
+        
+        let (session_id, _, _, code) = manager.create_room(host, "A".to_string(), config).await.unwrap();
+        assert_eq!(code.len(), ROOM_CODE_LEN);
+        assert!(code.bytes().all(|c| ROOM_CODE_CHARS.contains(&c)));
+        
+        // Public matchmaking never lands in a room, even with the same rules
+        let (public_session, _, _) = manager.join_or_create_game(stranger, "S".to_string(), config).await.unwrap();
+        assert_ne!(public_session, session_id);
+        assert!(manager.join_room(Uuid::new_v4(), "X".to_string(), "NOPE00").await.is_err());
+        
+        let (joined, player_id, _) = manager.join_room(friend, "B".to_string(), &code.to_lowercase()).await.unwrap();
+        assert_eq!((joined, player_id), (session_id, PlayerId::PLAYER_TWO));
+        assert!(manager.check_game_ready(session_id).await.is_some());
+        assert_eq!(manager.session(&session_id).unwrap().game_state.read().await.config.variant, RuleVariant::Pisti);
+        
+        // A full room's code stops working
+        assert!(manager.join_room(Uuid::new_v4(), "C".to_string(), &code).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_unjoined_room_expires() {
+        let manager = SessionManager::new();
+        let (host, guest) = (Uuid::new_v4(), Uuid::new_v4());
+        let config = GameConfig { mode: GameMode::ThreePlayer, ..Default::default() };
+        let (session_id, _, _, code) = manager.create_room(host, "A".to_string(), config).await.unwrap();
+        manager.join_room(guest, "B".to_string(), &code).await.unwrap();
+        
+        assert!(manager.cleanup_expired_sessions().await.is_empty());
+        assert!(manager.rooms.contains_key(&code));
+        
+        // Everyone seated at the part-filled table hears that it closed
+        manager.rooms.get_mut(&code).unwrap().created_at = Instant::now() - ROOM_TIMEOUT - Duration::from_secs(1);
+        let closed = manager.cleanup_expired_sessions().await;
+        assert_eq!(closed.len(), 2);
+        assert!(closed.iter().all(|(_, msg)| matches!(msg, ServerMessage::RoomClosed { code: closed } if *closed == Some(code.clone()))));
+        assert!(!manager.rooms.contains_key(&code));
+        assert!(!manager.sessions.contains_key(&session_id));
+        
+        // The host is free to play elsewhere
+        assert!(manager.join_or_create_game(host, "A".to_string(), GameConfig::default()).await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_queued_table_times_out() {
+        let manager = SessionManager::new();
+        let player = Uuid::new_v4();
+        let (session_id, _, _) = manager.join_or_create_game(player, "A".to_string(), GameConfig::default()).await.unwrap();
+        assert!(manager.cleanup_expired_sessions().await.is_empty());
+        
+        manager.waiting_queue.write().await[0].joined_at = Instant::now() - WAITING_TIMEOUT - Duration::from_secs(1);
+        let closed = manager.cleanup_expired_sessions().await;
+        assert!(matches!(closed.as_slice(), [(conn, ServerMessage::RoomClosed { code: None })] if *conn == player));
+        assert!(!manager.sessions.contains_key(&session_id));
+        assert!(manager.waiting_queue.read().await.is_empty());
+        
+        // The player can queue again and is matched with the next one
+        let (again, _, _) = manager.join_or_create_game(player, "A".to_string(), GameConfig::default()).await.unwrap();
+        assert_ne!(again, session_id);
+        let (joined, _, _) = manager.join_or_create_game(Uuid::new_v4(), "B".to_string(), GameConfig::default()).await.unwrap();
+        assert_eq!(joined, again);
+    }
+    
+    #[tokio::test]
+    async fn test_rematch_swaps_lead_and_keeps_tally() {
+        let manager = SessionManager::new();
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let (session_id, _, _) = manager.join_or_create_game(p1, "A".to_string(), GameConfig::default()).await.unwrap();
+        manager.join_or_create_game(p2, "B".to_string(), GameConfig::default()).await.unwrap();
+        manager.check_game_ready(session_id).await.unwrap();
+        assert!(manager.rematch(session_id, p1, false).await.is_err());
+        
+        let mut last = Vec::new();
+        loop {
+            let (conn, card) = {
+                let session = manager.session(&session_id).unwrap();
+                let state = session.game_state.read().await;
+                if state.game_over {
+                    break;
+                }
+                let conn = if state.current_turn == PlayerId::PLAYER_ONE { p1 } else { p2 };
+                (conn, state.player_hands[state.current_turn.index()][0])
+            };
+            last = manager.play_card(session_id, conn, card).await.unwrap();
+        }
+        assert!(last.iter().any(|(_, msg)| matches!(msg, ServerMessage::HeadToHead { tally } if tally.games() == 1)));
+        let first_lead = manager.session(&session_id).unwrap().game_state.read().await.opening_lead;
+        
+        // Accepting needs a request first, and the game waits for both players
+        assert!(manager.rematch(session_id, p2, true).await.is_err());
+        let asked = manager.rematch(session_id, p1, false).await.unwrap();
+        assert!(matches!(asked.as_slice(), [(conn, ServerMessage::RematchRequested { player: PlayerId::PLAYER_ONE })] if *conn == p2));
+        
+        let started = manager.rematch(session_id, p2, true).await.unwrap();
+        assert!(started.iter().any(|(_, msg)| matches!(msg, ServerMessage::HeadToHead { tally } if tally.games() == 1)));
+        assert_eq!(started.iter().filter(|(_, msg)| matches!(msg, ServerMessage::GameStarted { .. })).count(), 2);
+        
+        let session = manager.session(&session_id).unwrap();
+        let state = session.game_state.read().await;
+        assert!(!state.game_over && state.move_history.is_empty());
+        assert_eq!(state.opening_lead, first_lead.next(2));
+    }
+    
+    #[tokio::test]
+    async fn test_chat_mutes_limits_and_logs() {
+        let manager = SessionManager::new();
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let (session_id, _, _) = manager.join_or_create_game(p1, "A".to_string(), GameConfig::default()).await.unwrap();
+        manager.join_or_create_game(p2, "B".to_string(), GameConfig::default()).await.unwrap();
+        
+        let sent = manager.chat(session_id, p1, " hello ").await.unwrap();
+        assert_eq!(sent.len(), 2);
+        assert!(matches!(&sent[1], (conn, ServerMessage::Chat { name, text, .. }) if *conn == p2 && name == "A" && text == "hello"));
+        assert!(manager.chat(session_id, p1, &"x".repeat(MAX_CHAT_LEN + 1)).await.is_err());
+        assert!(manager.chat(session_id, Uuid::new_v4(), "hi").await.is_err());
+        
+        // Muting only silences the speaker for the one who muted them
+        manager.mute(session_id, p2, PlayerId::PLAYER_ONE, true).await.unwrap();
+        let sent = manager.emote(session_id, p1, Emote::GoodGame).await.unwrap();
+        assert!(sent.iter().all(|(conn, _)| *conn == p1));
+        let sent = manager.emote(session_id, p2, Emote::NiceKseri).await.unwrap();
+        assert_eq!(sent.len(), 2);
+        assert!(manager.mute(session_id, p2, PlayerId::PLAYER_TWO, true).await.is_err());
+        
+        // Five messages per window, rejected ones are not logged
+        for _ in 0..3 {
+            manager.chat(session_id, p1, "spam").await.unwrap();
+        }
+        assert!(manager.chat(session_id, p1, "spam").await.is_err());
+        
+        let log = manager.event_log(session_id).await.unwrap();
+        let chat_lines = log.events().iter()
+            .filter(|event| matches!(event.event_type, GameEventType::Chat { .. } | GameEventType::Emote { .. }))
+            .count();
+        assert_eq!(chat_lines, 6);
+    }
+    
+    #[tokio::test]
+    async fn test_reconnect_replays_missed_messages() {
+        let manager = SessionManager::new();
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let (session_id, _, token) = manager.join_or_create_game(p1, "A".to_string(), GameConfig::default()).await.unwrap();
+        manager.join_or_create_game(p2, "B".to_string(), GameConfig::default()).await.unwrap();
+        
+        let start = manager.sequence(manager.check_game_ready(session_id).await.unwrap()).await;
+        let seen = start.iter()
+            .filter(|(conn, _)| *conn == p1)
+            .filter_map(|(_, msg)| match msg {
+                ServerMessage::Sequenced { seq, .. } => Some(*seq),
+                _ => None,
+            })
+            .max()
+            .unwrap();
+        
+        // Messages for the seat are still numbered while it is away
+        manager.handle_player_disconnect(session_id, p1).await;
+        let missed = manager.sequence(manager.chat(session_id, p2, "still there?").await.unwrap()).await;
+        assert!(missed.iter().any(|(conn, msg)| *conn == p1 && matches!(msg, ServerMessage::Sequenced { seq, .. } if *seq == seen + 1)));
+        
+        let p1_again = Uuid::new_v4();
+        let (_, _, messages) = manager.reconnect_player(p1_again, token.clone(), Some(seen), true).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(&messages[0], ServerMessage::Sequenced { seq, message } if *seq == seen + 1 && matches!(**message, ServerMessage::Chat { .. })));
+        assert!(matches!(&messages[1], ServerMessage::Sequenced { seq, message } if *seq == seen + 2 && matches!(**message, ServerMessage::Connected { .. })));
+        
+        // Numbering goes on for the new connection
+        let next = manager.sequence(manager.chat(session_id, p2, "welcome back").await.unwrap()).await;
+        assert!(next.iter().any(|(conn, msg)| *conn == p1_again && matches!(msg, ServerMessage::Sequenced { seq, .. } if *seq == seen + 3)));
+        
+        // A client that saw more than was ever sent gets a snapshot with its hand
+        manager.handle_player_disconnect(session_id, p1_again).await;
+        let p1_third = Uuid::new_v4();
+        let (_, _, messages) = manager.reconnect_player(p1_third, token.clone(), Some(seen + 100), true).await.unwrap();
+        match &messages[1] {
+            ServerMessage::Resync { seq, hand, state } => {
+                assert_eq!(*seq, seen + 4);
+                assert_eq!(hand.len(), 4);
+                assert!(matches!(**state, ServerMessage::StateUpdate { hand_count: 4, .. }));
+            }
+            other => panic!("expected Resync, got {:?}", other),
+        }
+        
+        // Without the resume capability `last_seq` is ignored and the state is numbered as usual
+        manager.handle_player_disconnect(session_id, p1_third).await;
+        let (_, _, messages) = manager.reconnect_player(Uuid::new_v4(), token, Some(seen), false).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(&messages[1], ServerMessage::Sequenced { seq, message } if *seq == seen + 6 && matches!(**message, ServerMessage::StateUpdate { .. })));
+    }
+    
+    #[tokio::test]
+    async fn test_seat_remembers_commands_across_reconnect() {
+        let manager = SessionManager::new();
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let (session_id, _, token) = manager.join_or_create_game(p1, "A".to_string(), GameConfig::default()).await.unwrap();
+        manager.join_or_create_game(p2, "B".to_string(), GameConfig::default()).await.unwrap();
+        manager.check_game_ready(session_id).await.unwrap();
+        
+        // Commands are kept per seat; connections without one have nowhere to keep them
+        assert!(manager.record_command(p1, 7, Ok(())).await);
+        assert!(manager.record_command(p2, 7, Err(NackReason::NotYourTurn)).await);
+        assert!(!manager.record_command(Uuid::new_v4(), 7, Ok(())).await);
+        assert_eq!(manager.command_outcome(p2, 7).await, Some(Err(NackReason::NotYourTurn)));
+        
+        manager.handle_player_disconnect(session_id, p1).await;
+        let p1_again = Uuid::new_v4();
+        manager.reconnect_player(p1_again, token, None, false).await.unwrap();
+        assert_eq!(manager.command_outcome(p1_again, 7).await, Some(Ok(())));
+        assert_eq!(manager.command_outcome(p1_again, 8).await, None);
+        assert_eq!(manager.command_outcome(p1, 7).await, None);
+    }
+    
+    #[test]
+    fn test_turn_clock_capability_needs_a_clock() {
+        assert!(!SessionManager::new().capabilities().contains(&Capability::TurnClock));
+        let timed = SessionManager::new().with_turn_clock(ClockConfig::default());
+        assert_eq!(timed.capabilities().len(), Capability::all().len());
+    }
+    
+    #[tokio::test]
+    async fn test_account_keeps_identity_across_logins() {
+        let manager = SessionManager::new();
+        let (registered, rating) = manager.register_account("alice", "correct horse").await.unwrap();
+        assert_eq!(rating, 1500);
+        
+        // A second device logs in with the password, a third with the token
+        let (login, _) = manager.login("alice", "correct horse").await.unwrap();
+        assert_eq!(login.identity, registered.identity);
+        let (login, _) = manager.login_with_token(&registered.token).await.unwrap();
+        assert_eq!(login.identity, registered.identity);
+        assert_eq!(manager.ratings.get(&login.identity).await.unwrap().name, "alice");
+        assert!(manager.login("alice", "wrong horse").await.is_err());
+        
+        // Knowing the identity is not enough to play as the account
+        assert!(manager.identify(Some(registered.identity)).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_rated_game_updates_leaderboard() {
+        let manager = SessionManager::new();
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let (id1, rating) = manager.identify(None).await.unwrap();
+        assert_eq!(rating, 1500);
+        assert_eq!(manager.identify(Some(id1.clone())).await.unwrap().0, id1);
+        // Unknown identities are not taken over, a new one is handed out
+        assert_ne!(manager.identify(Some("made-up".to_string())).await.unwrap().0, "made-up");
+        let (id2, _) = manager.identify(None).await.unwrap();
+        
+        let (session_id, _, _) = manager.join_or_create_game(p1, "A".to_string(), GameConfig::default()).await.unwrap();
+        manager.set_identity(session_id, p1, id1).await;
+        manager.join_or_create_game(p2, "B".to_string(), GameConfig::default()).await.unwrap();
+        manager.set_identity(session_id, p2, id2).await;
+        
+        let start = manager.check_game_ready(session_id).await.unwrap();
+        assert!(matches!(&start[0].1, ServerMessage::GameStarted { player_ratings, .. } if *player_ratings == vec![Some(1500), Some(1500)]));
+        
+        let mut last = Vec::new();
+        loop {
+            let (conn, card) = {
+                let session = manager.session(&session_id).unwrap();
+                let state = session.game_state.read().await;
+                if state.game_over {
+                    break;
+                }
+                let conn = if state.current_turn == PlayerId::PLAYER_ONE { p1 } else { p2 };
+                (conn, state.player_hands[state.current_turn.index()][0])
+            };
+            last = manager.play_card(session_id, conn, card).await.unwrap();
+        }
+        
+        let changes = last.iter().filter(|(conn, msg)| *conn == p1 && matches!(msg, ServerMessage::RatingChanged { .. })).count();
+        assert_eq!(changes, 2);
+        let board = manager.leaderboard(10).await;
+        assert_eq!(board.len(), 2);
+        assert!(board.iter().all(|entry| entry.games == 1));
+        let winner = manager.session(&session_id).unwrap().game_state.read().await.get_winning_team();
+        if let Some(team) = winner {
+            assert_eq!(board[0].name, ["A", "B"][team]);
+            assert!(board[0].rating > 1500);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_game_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("kseri-restart-{}", Uuid::new_v4()));
+        let manager = SessionManager::new().with_store(SessionStore::new(&dir).unwrap());
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        
+        let (session_id, _, token) = manager.join_or_create_game(p1, "A".to_string(), GameConfig::default()).await.unwrap();
+        manager.join_or_create_game(p2, "B".to_string(), GameConfig::default()).await.unwrap();
+        manager.check_game_ready(session_id).await.unwrap();
+        let card = manager.session(&session_id).unwrap().game_state.read().await.player_hands[0][0];
+        manager.play_card(session_id, p1, card).await.unwrap();
+        
+        // A new server process picks the game up from disk
+        let restarted = SessionManager::new().with_store(SessionStore::new(&dir).unwrap());
+        assert_eq!(restarted.restore_sessions().unwrap(), 1);
+        
+        let (reconnected_session, player_id, messages) = restarted.reconnect_player(Uuid::new_v4(), token, None, false).await.unwrap();
+        assert_eq!(reconnected_session, session_id);
+        assert_eq!(player_id, PlayerId::PLAYER_ONE);
+        assert!(matches!(&messages[1], ServerMessage::Sequenced { message, .. }
+            if matches!(**message, ServerMessage::StateUpdate { hand_count: 3, your_turn: false, .. })));
+        let log = restarted.event_log(session_id).await.unwrap();
+        assert_eq!(log.rebuild().unwrap().move_history.len(), 1);
+        
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,735 @@
+//! Compact binary encoding of the protocol messages, negotiated per connection next to JSON.
+//!
+//! Every value starts with a tag byte. Bytes `0x80..=0xFF` are the integers 0 to 127 on
+//! their own, so cards, seats and enum variants take one byte. Structs are maps keyed by
+//! field index instead of name, and enum variants are sent by index, so the same message
+//! enums round-trip through both encodings.
+
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+use serde::{Deserialize, Serialize as SerializeDerive};
+
+const NIL: u8 = 0x00;
+const FALSE: u8 = 0x01;
+const TRUE: u8 = 0x02;
+const UINT: u8 = 0x03;
+const NEG_INT: u8 = 0x04;
+const FLOAT: u8 = 0x05;
+const STR: u8 = 0x06;
+const BYTES: u8 = 0x07;
+const ARRAY: u8 = 0x08;
+const MAP: u8 = 0x09;
+/// Enum variant with data: variant index, then the data
+const VARIANT: u8 = 0x0A;
+/// Integers below this are the tag byte `0x80 + n`
+const FIXINT_LIMIT: u64 = 0x80;
+
+/// How messages on a connection are encoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, SerializeDerive, Deserialize)]
+pub enum WireFormat {
+    /// JSON in text frames
+    #[default]
+    Json,
+    /// This module's encoding in binary frames
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WireError(String);
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for WireError {}
+
+impl ser::Error for WireError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        WireError(msg.to_string())
+    }
+}
+
+impl de::Error for WireError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        WireError(msg.to_string())
+    }
+}
+
+type Result<T> = std::result::Result<T, WireError>;
+
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut serializer = Serializer { out: Vec::new() };
+    value.serialize(&mut serializer)?;
+    Ok(serializer.out)
+}
+
+pub fn from_bytes<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T> {
+    let mut deserializer = Deserializer { input: bytes };
+    let value = T::deserialize(&mut deserializer)?;
+    if !deserializer.input.is_empty() {
+        return Err(WireError(format!("{} trailing bytes", deserializer.input.len())));
+    }
+    Ok(value)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// Tag and length of an array or map, ahead of its `count` items
+fn header(tag: u8, count: u64) -> Vec<u8> {
+    let mut out = vec![tag];
+    write_varint(&mut out, count);
+    out
+}
+
+struct Serializer {
+    out: Vec<u8>,
+}
+
+impl Serializer {
+    fn write_uint(&mut self, n: u64) {
+        if n < FIXINT_LIMIT {
+            self.out.push(0x80 | n as u8);
+        } else {
+            self.out.push(UINT);
+            write_varint(&mut self.out, n);
+        }
+    }
+
+    fn write_int(&mut self, n: i64) {
+        if n >= 0 {
+            self.write_uint(n as u64);
+        } else {
+            self.out.push(NEG_INT);
+            write_varint(&mut self.out, !n as u64);
+        }
+    }
+
+    fn write_bytes(&mut self, tag: u8, bytes: &[u8]) {
+        self.out.push(tag);
+        write_varint(&mut self.out, bytes.len() as u64);
+        self.out.extend_from_slice(bytes);
+    }
+
+    /// Items are written first and counted; the header goes in front of them at the end
+    fn compound(&mut self, tag: u8) -> Compound<'_> {
+        let start = self.out.len();
+        Compound { ser: self, tag, start, count: 0, next_field: 0 }
+    }
+}
+
+struct Compound<'a> {
+    ser: &'a mut Serializer,
+    tag: u8,
+    start: usize,
+    count: u64,
+    /// Index of the next struct field, skipped ones included
+    next_field: u64,
+}
+
+impl Compound<'_> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.count += 1;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.ser.write_uint(self.next_field);
+        self.next_field += 1;
+        self.element(value)
+    }
+
+    fn finish(self) -> Result<()> {
+        let header = header(self.tag, self.count);
+        self.ser.out.splice(self.start..self.start, header);
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = WireError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.out.push(if v { TRUE } else { FALSE });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.write_int(v);
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.write_uint(v);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.out.push(FLOAT);
+        self.out.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.write_bytes(STR, v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.write_bytes(BYTES, v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    /// `Some` is the value itself, so an `Option` of a unit type cannot be told from `None`
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        self.out.push(NIL);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str) -> Result<()> {
+        self.serialize_u32(variant_index)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.out.push(VARIANT);
+        self.write_uint(variant_index as u64);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(self.compound(ARRAY))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Ok(self.compound(ARRAY))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Ok(self.compound(ARRAY))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        self.out.push(VARIANT);
+        self.write_uint(variant_index as u64);
+        Ok(self.compound(ARRAY))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(self.compound(MAP))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(self.compound(MAP))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        self.out.push(VARIANT);
+        self.write_uint(variant_index as u64);
+        Ok(self.compound(MAP))
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = WireError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = WireError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = WireError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = WireError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = WireError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.element(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = WireError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<()> {
+        self.field(value)
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<()> {
+        self.next_field += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = WireError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<()> {
+        self.field(value)
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<()> {
+        self.next_field += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+struct Deserializer<'de> {
+    input: &'de [u8],
+}
+
+impl<'de> Deserializer<'de> {
+    fn peek(&self) -> Result<u8> {
+        self.input.first().copied().ok_or_else(|| WireError("unexpected end of message".to_string()))
+    }
+
+    fn next(&mut self) -> Result<u8> {
+        let byte = self.peek()?;
+        self.input = &self.input[1..];
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'de [u8]> {
+        if len > self.input.len() {
+            return Err(WireError("unexpected end of message".to_string()));
+        }
+        let (taken, rest) = self.input.split_at(len);
+        self.input = rest;
+        Ok(taken)
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.next()?;
+            n |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(WireError("varint is too long".to_string()))
+    }
+
+    /// A length that cannot be longer than what is left of the message
+    fn len(&mut self) -> Result<usize> {
+        let len = self.varint()?;
+        if len > self.input.len() as u64 {
+            return Err(WireError(format!("length {} is past the end of the message", len)));
+        }
+        Ok(len as usize)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = WireError;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.next()? {
+            tag if tag as u64 >= FIXINT_LIMIT => visitor.visit_u64((tag & 0x7F) as u64),
+            NIL => visitor.visit_unit(),
+            FALSE => visitor.visit_bool(false),
+            TRUE => visitor.visit_bool(true),
+            UINT => visitor.visit_u64(self.varint()?),
+            NEG_INT => visitor.visit_i64(!(self.varint()? as i64)),
+            FLOAT => {
+                let bytes = self.take(8)?;
+                visitor.visit_f64(f64::from_le_bytes(bytes.try_into().expect("took 8 bytes")))
+            }
+            STR => {
+                let len = self.len()?;
+                let bytes = self.take(len)?;
+                visitor.visit_borrowed_str(std::str::from_utf8(bytes).map_err(|e| WireError(e.to_string()))?)
+            }
+            BYTES => {
+                let len = self.len()?;
+                visitor.visit_borrowed_bytes(self.take(len)?)
+            }
+            ARRAY => {
+                let remaining = self.len()?;
+                visitor.visit_seq(Items { de: self, remaining })
+            }
+            MAP => {
+                let remaining = self.len()?;
+                visitor.visit_map(Items { de: self, remaining })
+            }
+            // A variant outside an enum reads as a map from its index to its data
+            VARIANT => visitor.visit_map(Items { de: self, remaining: 1 }),
+            tag => Err(WireError(format!("unknown tag {:#04x}", tag))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.peek()? == NIL {
+            self.next()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let has_data = self.peek()? == VARIANT;
+        if has_data {
+            self.next()?;
+        }
+        visitor.visit_enum(Variant { de: self, has_data })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Elements of an array, or entries of a map
+struct Items<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    remaining: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for Items<'_, 'de> {
+    type Error = WireError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Items<'_, 'de> {
+    type Error = WireError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+/// An enum value: a bare variant index, or `VARIANT`, the index and the variant's data
+struct Variant<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    has_data: bool,
+}
+
+impl<'de> de::EnumAccess<'de> for Variant<'_, 'de> {
+    type Error = WireError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Variant<'_, 'de> {
+    type Error = WireError;
+
+    fn unit_variant(self) -> Result<()> {
+        if self.has_data {
+            // Unit variants may still come with an explicit `()`
+            return <() as Deserialize>::deserialize(&mut *self.de);
+        }
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        if !self.has_data {
+            return seed.deserialize(().into_deserializer());
+        }
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        if !self.has_data {
+            return Err(WireError("expected tuple variant data".to_string()));
+        }
+        de::Deserializer::deserialize_any(&mut *self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        if !self.has_data {
+            return Err(WireError("expected struct variant data".to_string()));
+        }
+        de::Deserializer::deserialize_any(&mut *self.de, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::card::{Card, PlayerId, Rank, Suit};
+    use crate::server::chat::Emote;
+    use crate::server::commands::NackReason;
+    use crate::server::limits::Rejection;
+    use crate::server::messages::{ClientMessage, ServerMessage};
+    use crate::server::rules::GameConfig;
+
+    fn round_trip<T>(value: &T) -> Vec<u8>
+    where
+        T: Serialize + for<'de> Deserialize<'de> + fmt::Debug + PartialEq,
+    {
+        let bytes = to_bytes(value).unwrap();
+        assert_eq!(&from_bytes::<T>(&bytes).unwrap(), value);
+        bytes
+    }
+
+    #[test]
+    fn test_values_round_trip() {
+        round_trip(&0u8);
+        round_trip(&127u32);
+        round_trip(&128u32);
+        round_trip(&u64::MAX);
+        round_trip(&-1i64);
+        round_trip(&i64::MIN);
+        round_trip(&1.5f64);
+        round_trip(&"kseri".to_string());
+        round_trip(&Some(3u32));
+        round_trip(&None::<u32>);
+        round_trip(&vec![(1u8, "a".to_string()), (2, "b".to_string())]);
+        round_trip(&Rejection::RateLimited { retry_after_ms: 250 });
+    }
+
+    #[test]
+    fn test_cards_take_one_byte() {
+        for index in 0..52 {
+            let card = Card::from_index(index).unwrap();
+            assert_eq!(round_trip(&card).len(), 1);
+        }
+        assert!(Card::from_index(52).is_none());
+        // JSON keeps its field names
+        let json = serde_json::to_string(&Card::new(Suit::Hearts, Rank::Ace)).unwrap();
+        assert_eq!(json, r#"{"suit":"Hearts","rank":"Ace"}"#);
+    }
+
+    #[test]
+    fn test_messages_round_trip() {
+        let play = ClientMessage::PlayCard { card: Card::new(Suit::Spades, Rank::King) };
+        let bytes = to_bytes(&play).unwrap();
+        assert_eq!(bytes.len(), 9);
+        assert!(bytes.len() * 5 < serde_json::to_vec(&play).unwrap().len());
+        assert_eq!(format!("{:?}", from_bytes::<ClientMessage>(&bytes).unwrap()), format!("{:?}", play));
+
+        let config = GameConfig::default();
+        let join = ClientMessage::JoinQueue {
+            player_name: "Alice".to_string(),
+            variant: config.variant,
+            mode: config.mode,
+            target_score: None,
+            bot: None,
+        };
+        let decoded: ClientMessage = from_bytes(&to_bytes(&join).unwrap()).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", join));
+
+        let command = ClientMessage::Command { id: 42, command: Box::new(play) };
+        let decoded: ClientMessage = from_bytes(&to_bytes(&command).unwrap()).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", command));
+
+        let messages = vec![
+            ServerMessage::Pong,
+            ServerMessage::Emote { player: PlayerId::PLAYER_TWO, emote: Emote::GoodGame },
+            ServerMessage::CardPlayed {
+                player: PlayerId::PLAYER_ONE,
+                card: Card::new(Suit::Diamonds, Rank::Ten),
+                captured_cards: vec![Card::new(Suit::Clubs, Rank::Ten)],
+                hidden_captured: 0,
+                is_kseri: true,
+            },
+            ServerMessage::Rejected { reason: Rejection::Banned { retry_after_ms: 300_000 } },
+            ServerMessage::Ack { id: 42 },
+            ServerMessage::Nack { id: 43, reason: NackReason::Rejected(Rejection::InvalidName { reason: "Name is empty".to_string() }) },
+            ServerMessage::RoomClosed { code: Some("K7QM2X".to_string()) },
+        ];
+        for msg in messages {
+            let decoded: ServerMessage = from_bytes(&to_bytes(&msg).unwrap()).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", msg));
+        }
+    }
+
+    #[test]
+    fn test_malformed_input_is_an_error() {
+        assert!(from_bytes::<ClientMessage>(&[]).is_err());
+        assert!(from_bytes::<ClientMessage>(&[MAP, 0x05, 0x80]).is_err());
+        assert!(from_bytes::<ClientMessage>(&[STR, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F]).is_err());
+        assert!(from_bytes::<u32>(&[0x81, 0x82]).is_err());
+        assert!(from_bytes::<Card>(&[0x80 | 60]).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use crate::components::card::PlayerId;
+use crate::resources::GameSettings;
+use crate::server::chat::{clean_chat, Emote, MAX_CHAT_LEN};
+use crate::server::messages::{ClientMessage, ServerMessage};
+
+/// Chat lines kept on screen
+pub const CHAT_HISTORY: usize = 8;
+/// F1..F5 send the emotes of `Emote::all` in order
+pub const EMOTE_KEYS: [KeyCode; 5] = [KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5];
+
+/// Chat shown next to the table and the messages typed into it
+#[derive(Resource, Default)]
+pub struct ChatPanel {
+    /// Lines on screen, oldest first
+    pub lines: VecDeque<String>,
+    pub input: String,
+    pub typing: bool,
+    /// Seats whose chat is hidden
+    pub muted: Vec<PlayerId>,
+    /// Messages for the server, taken by the network layer
+    pub outbox: Vec<ClientMessage>,
+}
+
+impl ChatPanel {
+    pub fn push_line(&mut self, line: String) {
+        self.lines.push_back(line);
+        while self.lines.len() > CHAT_HISTORY {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Show a `Chat` or `Emote` from the server; `names` are indexed by seat
+    pub fn receive(&mut self, msg: &ServerMessage, names: &[String]) {
+        let (player, text) = match msg {
+            ServerMessage::Chat { player, text, .. } => (*player, text.clone()),
+            ServerMessage::Emote { player, emote } => (*player, emote.text().to_string()),
+            _ => return,
+        };
+        if self.muted.contains(&player) {
+            return;
+        }
+
+        let name = names.get(player.index()).cloned().unwrap_or_else(|| format!("P{}", player.index() + 1));
+        self.push_line(format!("{}: {}", name, text));
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if self.input.chars().count() >= MAX_CHAT_LEN {
+                break;
+            }
+            self.input.push(c);
+        }
+    }
+
+    /// Queue the typed line for sending and close the input
+    pub fn submit(&mut self) {
+        let input = std::mem::take(&mut self.input);
+        self.typing = false;
+        if let Ok(text) = clean_chat(&input) {
+            self.outbox.push(ClientMessage::Chat { text });
+        }
+    }
+
+    pub fn send_emote(&mut self, emote: Emote) {
+        self.outbox.push(ClientMessage::Emote { emote });
+    }
+
+    /// Hide or show `player`'s chat, here and on the server
+    pub fn toggle_mute(&mut self, player: PlayerId) {
+        let muted = !self.muted.contains(&player);
+        self.muted.retain(|seat| *seat != player);
+        if muted {
+            self.muted.push(player);
+        }
+        self.outbox.push(ClientMessage::Mute { player, muted });
+    }
+}
+
+#[derive(Component)]
+pub struct ChatPanelText;
+
+pub fn setup_chat_panel(mut commands: Commands) {
+    commands.spawn((
+        ChatPanelText,
+        Text2d::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Anchor::BottomLeft,
+        Transform::from_xyz(120.0, -290.0, 201.0),
+    ));
+}
+
+pub fn cleanup_chat_panel(mut commands: Commands, query: Query<Entity, With<ChatPanelText>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Enter opens the input and sends the line, Escape cancels it.
+/// Outside the input F1..F5 send emotes and F6 mutes the opponent.
+pub fn chat_input(
+    mut events: EventReader<KeyboardInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut panel: ResMut<ChatPanel>,
+) {
+    if !panel.typing {
+        events.clear();
+        if keys.just_pressed(KeyCode::Enter) {
+            panel.typing = true;
+        }
+        for (key, emote) in EMOTE_KEYS.iter().zip(Emote::all()) {
+            if keys.just_pressed(*key) {
+                panel.send_emote(emote);
+            }
+        }
+        if keys.just_pressed(KeyCode::F6) {
+            panel.toggle_mute(PlayerId::PLAYER_TWO);
+        }
+        return;
+    }
+
+    for event in events.read().filter(|event| event.state == ButtonState::Pressed) {
+        match &event.logical_key {
+            Key::Enter => panel.submit(),
+            Key::Escape => {
+                panel.input.clear();
+                panel.typing = false;
+            }
+            Key::Backspace => {
+                panel.input.pop();
+            }
+            Key::Space => panel.type_text(" "),
+            Key::Character(text) => panel.type_text(text),
+            _ => {}
+        }
+    }
+}
+
+pub fn update_chat_panel(
+    panel: Res<ChatPanel>,
+    settings: Res<GameSettings>,
+    mut query: Query<&mut Text2d, With<ChatPanelText>>,
+) {
+    let Ok(mut text) = query.single_mut() else {
+        return;
+    };
+
+    let mut shown: Vec<String> = panel.lines.iter().cloned().collect();
+    if panel.typing {
+        shown.push(format!("> {}_", panel.input));
+    } else {
+        shown.push("Enter chat  F1-F5 emotes  F6 mute".to_string());
+    }
+    if panel.muted.contains(&PlayerId::PLAYER_TWO) {
+        shown.push(format!("{} is muted (F6)", settings.opponent_name));
+    }
+    let shown = shown.join("\n");
+    if text.0 != shown {
+        text.0 = shown;
+    }
+}
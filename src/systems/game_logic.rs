@@ -9,6 +9,8 @@ pub enum GameState {
     Playing,
     GameOver,
     GameSetup,
+    /// Stepping through a recorded game; the turn systems stay idle
+    Replay,
 }
 
 #[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
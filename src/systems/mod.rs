@@ -8,6 +8,10 @@ pub mod state_transitions;
 pub mod turn_management;
 pub mod validation;
 pub mod ui;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod replay;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod chat;
 
 #[cfg(test)]
 pub mod tests;
@@ -23,4 +27,8 @@ pub use rendering::*;
 pub use state_transitions::*;
 pub use turn_management::*;
 pub use validation::*;
-pub use ui::*;
\ No newline at end of file
+pub use ui::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use replay::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use chat::*;
\ No newline at end of file
@@ -0,0 +1,285 @@
+use bevy::prelude::*;
+use crate::components::card::{Card, CardLocation, CardPosition, PlayerId};
+use crate::server::game_engine::KseriGameState;
+use crate::server::record::{card_code, GameRecord};
+use crate::systems::game_logic::{GameState, TurnManager};
+use crate::systems::rendering::CardVisual;
+use crate::systems::ui::{DeckCounter, ScoreDisplay};
+
+/// Autoplay speeds in moves per second; Up and Down cycle through them
+pub const REPLAY_SPEEDS: [f32; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+
+/// A recorded game opened for viewing, with the position after every move
+#[derive(Resource)]
+pub struct ReplayViewer {
+    pub record: GameRecord,
+    /// Game after each move; entry 0 is the opening deal
+    pub positions: Vec<KseriGameState>,
+    /// Moves applied so far, an index into `positions`
+    pub cursor: usize,
+    /// Running while autoplay is on
+    pub autoplay: Option<Timer>,
+    pub speed_index: usize,
+    /// Digits typed so far for jump-to-move, applied with Enter
+    pub jump_input: String,
+}
+
+impl ReplayViewer {
+    /// Validate the record and compute every position of the game
+    pub fn new(record: GameRecord) -> Result<Self, String> {
+        let positions = record.replay()?;
+
+        Ok(Self {
+            record,
+            positions,
+            cursor: 0,
+            autoplay: None,
+            speed_index: 1,
+            jump_input: String::new(),
+        })
+    }
+
+    /// Open a record file written by the server
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::new(GameRecord::parse(&text)?)
+    }
+
+    pub fn move_count(&self) -> usize {
+        self.record.plays.len()
+    }
+
+    pub fn current(&self) -> &KseriGameState {
+        &self.positions[self.cursor]
+    }
+
+    pub fn step_forward(&mut self) -> bool {
+        self.jump_to(self.cursor + 1)
+    }
+
+    pub fn step_back(&mut self) -> bool {
+        self.cursor > 0 && self.jump_to(self.cursor - 1)
+    }
+
+    /// Show the game after `moves` plays; returns false when out of range
+    pub fn jump_to(&mut self, moves: usize) -> bool {
+        if moves > self.move_count() {
+            return false;
+        }
+        self.cursor = moves;
+        true
+    }
+
+    /// Moves per second while autoplaying
+    pub fn speed(&self) -> f32 {
+        REPLAY_SPEEDS[self.speed_index]
+    }
+
+    pub fn toggle_autoplay(&mut self) {
+        self.autoplay = match self.autoplay {
+            Some(_) => None,
+            None => Some(Timer::from_seconds(1.0 / self.speed(), TimerMode::Repeating)),
+        };
+    }
+
+    /// Change speed by `steps` entries of `REPLAY_SPEEDS`, keeping autoplay running
+    pub fn change_speed(&mut self, steps: isize) {
+        self.speed_index = self.speed_index.saturating_add_signed(steps).min(REPLAY_SPEEDS.len() - 1);
+        if let Some(timer) = self.autoplay.as_mut() {
+            timer.set_duration(std::time::Duration::from_secs_f32(1.0 / REPLAY_SPEEDS[self.speed_index]));
+        }
+    }
+}
+
+/// Marks the card entities drawn for the current replay position
+#[derive(Component)]
+pub struct ReplayCard;
+
+/// Move counter and controls shown during a replay
+#[derive(Component)]
+pub struct ReplayStatus;
+
+/// Switch straight to the viewer when a record was opened at startup
+pub fn start_replay(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Replay);
+}
+
+pub fn setup_replay(mut commands: Commands) {
+    commands.spawn((
+        ReplayStatus,
+        Text2d::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Transform::from_xyz(0.0, -150.0, 201.0),
+    ));
+}
+
+pub fn cleanup_replay(
+    mut commands: Commands,
+    cards: Query<Entity, With<ReplayCard>>,
+    status: Query<Entity, With<ReplayStatus>>,
+) {
+    for entity in cards.iter().chain(status.iter()) {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Right/Left step, Home/End jump to either end, digits + Enter jump to a move,
+/// Space toggles autoplay, Up/Down change its speed and Escape leaves the viewer
+pub fn replay_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut viewer: ResMut<ReplayViewer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+        KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    ];
+
+    for key in keys.get_just_pressed() {
+        match key {
+            KeyCode::ArrowRight => {
+                viewer.step_forward();
+            }
+            KeyCode::ArrowLeft => {
+                viewer.step_back();
+            }
+            KeyCode::Home => {
+                viewer.jump_to(0);
+            }
+            KeyCode::End => {
+                let last = viewer.move_count();
+                viewer.jump_to(last);
+            }
+            KeyCode::Space => viewer.toggle_autoplay(),
+            KeyCode::ArrowUp => viewer.change_speed(1),
+            KeyCode::ArrowDown => viewer.change_speed(-1),
+            KeyCode::Enter => {
+                let input = std::mem::take(&mut viewer.jump_input);
+                if let Ok(moves) = input.parse() {
+                    viewer.jump_to(moves);
+                }
+            }
+            KeyCode::Backspace => {
+                viewer.jump_input.pop();
+            }
+            KeyCode::Escape => next_state.set(GameState::Menu),
+            digit => {
+                if let Some(value) = DIGITS.iter().position(|d| d == digit) {
+                    viewer.jump_input.push_str(&value.to_string());
+                }
+            }
+        }
+    }
+}
+
+pub fn replay_autoplay(time: Res<Time>, mut viewer: ResMut<ReplayViewer>) {
+    let Some(timer) = viewer.autoplay.as_mut() else {
+        return;
+    };
+
+    if timer.tick(time.delta()).just_finished() && !viewer.step_forward() {
+        // Reached the end of the game
+        viewer.autoplay = None;
+    }
+}
+
+/// Respawn the cards whenever the viewer moves to another position.
+/// The rendering systems pick the new entities up and lay them out like a live game.
+pub fn sync_replay_cards(
+    mut commands: Commands,
+    viewer: Res<ReplayViewer>,
+    mut shown: Local<Option<usize>>,
+    existing: Query<Entity, With<ReplayCard>>,
+) {
+    if *shown == Some(viewer.cursor) && !existing.is_empty() {
+        return;
+    }
+    *shown = Some(viewer.cursor);
+
+    for entity in existing.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let state = viewer.current();
+    let mut spawn = |card: Card, location: CardLocation, index: usize, face_up: bool| {
+        commands.spawn((
+            ReplayCard,
+            card,
+            CardPosition { location, index },
+            CardVisual { face_up, selected: false },
+        ));
+    };
+
+    for (index, card) in state.deck.iter().enumerate() {
+        spawn(*card, CardLocation::Deck, index, false);
+    }
+    for (index, card) in state.table_cards.iter().enumerate() {
+        spawn(*card, CardLocation::Table, index, index >= state.table_hidden);
+    }
+    // Both hands face up: a replay has nothing left to hide
+    for (seat, hand) in state.player_hands.iter().enumerate() {
+        for (index, card) in hand.iter().enumerate() {
+            spawn(*card, CardLocation::PlayerHand(PlayerId(seat as u8)), index, true);
+        }
+    }
+    for (seat, pile) in state.player_scores.iter().enumerate() {
+        for (index, card) in pile.iter().enumerate() {
+            spawn(*card, CardLocation::PlayerScore(PlayerId(seat as u8)), index, true);
+        }
+    }
+}
+
+/// Point the shared score, deck and turn displays at the replay position
+pub fn update_replay_table(
+    viewer: Res<ReplayViewer>,
+    mut turn_manager: ResMut<TurnManager>,
+    mut score_query: Query<(&mut Text2d, &ScoreDisplay)>,
+    mut deck_query: Query<&mut Text2d, (With<DeckCounter>, Without<ScoreDisplay>)>,
+) {
+    let state = viewer.current();
+    let mode = state.mode();
+    let scores = state.calculate_scores();
+
+    turn_manager.current_player = state.current_turn;
+
+    for (mut text, display) in score_query.iter_mut() {
+        if let Some(score) = scores.get(mode.team_of(display.player_id.index())) {
+            text.0 = format!("Score: {}", score);
+        }
+    }
+    if let Ok(mut text) = deck_query.single_mut() {
+        text.0 = format!("Deck: {}", state.deck.len());
+    }
+}
+
+pub fn update_replay_status(
+    viewer: Res<ReplayViewer>,
+    mut status_query: Query<&mut Text2d, With<ReplayStatus>>,
+) {
+    let Ok(mut text) = status_query.single_mut() else {
+        return;
+    };
+
+    let mut status = format!("Move {}/{}", viewer.cursor, viewer.move_count());
+    if let Some(play) = viewer.cursor.checked_sub(1).map(|idx| viewer.record.plays[idx]) {
+        status.push_str(&format!("  P{} {}", play.player.index() + 1, card_code(play.card)));
+        if play.captured > 0 {
+            status.push_str(&format!(" x{}", play.captured));
+        }
+        if play.is_kseri {
+            status.push_str(" KSERI");
+        }
+    }
+    if viewer.autoplay.is_some() {
+        status.push_str(&format!("  ▶ {} moves/s", viewer.speed()));
+    }
+    if !viewer.jump_input.is_empty() {
+        status.push_str(&format!("  go to {}_", viewer.jump_input));
+    }
+    status.push_str("\nLeft/Right step  Home/End  digits+Enter jump  Space autoplay  Up/Down speed  Esc leave");
+    text.0 = status;
+}
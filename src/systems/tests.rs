@@ -313,4 +313,73 @@ mod tests {
         assert_eq!(total, 37);
         assert_eq!(score.total_points, 37);
     }
-}
\ No newline at end of file
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_replay_viewer_steps_through_record() {
+        use crate::server::{GameRecord, KseriGameState};
+        use crate::systems::replay::{ReplayViewer, REPLAY_SPEEDS};
+        
+        let mut state = KseriGameState::with_seed(12);
+        while !state.game_over {
+            let player = state.current_turn;
+            let card = state.player_hands[player.index()][0];
+            state.play_card(player, card).unwrap();
+        }
+        let record = GameRecord::from_game(&state, vec!["A".into(), "B".into()], "2026.10.17".into()).unwrap();
+        let mut viewer = ReplayViewer::new(record).unwrap();
+        
+        assert_eq!(viewer.positions.len(), 49);
+        assert!(!viewer.step_back());
+        assert_eq!(viewer.current().player_hands[0].len(), 4);
+        
+        assert!(viewer.step_forward());
+        assert_eq!(viewer.current().move_history.len(), 1);
+        
+        assert!(viewer.jump_to(48));
+        assert!(!viewer.step_forward());
+        assert_eq!(viewer.current().player_scores, state.player_scores);
+        assert!(!viewer.jump_to(49));
+        
+        viewer.change_speed(10);
+        assert_eq!(viewer.speed(), REPLAY_SPEEDS[REPLAY_SPEEDS.len() - 1]);
+        viewer.change_speed(-10);
+        assert_eq!(viewer.speed(), REPLAY_SPEEDS[0]);
+    }
+    
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_chat_panel() {
+        use crate::server::{ClientMessage, Emote, ServerMessage, MAX_CHAT_LEN};
+        use crate::systems::chat::{ChatPanel, CHAT_HISTORY};
+        
+        let mut panel = ChatPanel::default();
+        let names = vec!["A".to_string(), "B".to_string()];
+        
+        panel.typing = true;
+        panel.type_text(&"x".repeat(MAX_CHAT_LEN + 10));
+        assert_eq!(panel.input.len(), MAX_CHAT_LEN);
+        panel.submit();
+        assert!(!panel.typing && panel.input.is_empty());
+        assert!(matches!(&panel.outbox[0], ClientMessage::Chat { text } if text.len() == MAX_CHAT_LEN));
+        
+        // Blank lines are not sent
+        panel.type_text("   ");
+        panel.submit();
+        assert_eq!(panel.outbox.len(), 1);
+        
+        panel.receive(&ServerMessage::Emote { player: PlayerId::PLAYER_TWO, emote: Emote::NiceKseri }, &names);
+        assert_eq!(panel.lines.back().unwrap(), "B: Nice kseri!");
+        
+        panel.toggle_mute(PlayerId::PLAYER_TWO);
+        assert!(matches!(panel.outbox.last(), Some(ClientMessage::Mute { muted: true, .. })));
+        panel.receive(&ServerMessage::Emote { player: PlayerId::PLAYER_TWO, emote: Emote::GoodGame }, &names);
+        assert_eq!(panel.lines.len(), 1);
+        
+        for i in 0..20 {
+            panel.push_line(i.to_string());
+        }
+        assert_eq!(panel.lines.len(), CHAT_HISTORY);
+        assert_eq!(panel.lines.back().unwrap(), "19");
+    }
+}